@@ -0,0 +1,25 @@
+//! Clocks shared by the poller, device discovery, history and exporters.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Current wall-clock time as Unix milliseconds.
+#[inline]
+pub fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Read a POSIX clock in seconds (`CLOCK_BOOTTIME`, `CLOCK_MONOTONIC`, …).
+#[inline]
+pub fn read_clock(clock: libc::clockid_t) -> f64 {
+    unsafe {
+        let mut ts: libc::timespec = std::mem::zeroed();
+        if libc::clock_gettime(clock, &mut ts) == 0 {
+            ts.tv_sec as f64 + ts.tv_nsec as f64 / 1e9
+        } else {
+            0.0
+        }
+    }
+}
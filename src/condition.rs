@@ -0,0 +1,408 @@
+//! Boolean conditions over the stats tree, e.g. `battery_level>=80`.
+//!
+//! ```text
+//! expr       := and ( ("||" | "or") and )*
+//! and        := unary ( ("&&" | "and") unary )*
+//! unary      := ("!" | "not") unary | "(" expr ")" | comparison
+//! comparison := path ( ">=" | "<=" | "==" | "!=" | ">" | "<" ) literal
+//! ```
+//!
+//! The left-hand side is any router path (`cpu_temp`, `cores/cpu4/cur_freq`,
+//! `cores/*/usage`, `cores[model_name=Cortex-X2]/usage`).  A comparison holds
+//! if **any** value the path resolves to satisfies it, so `cores/*/usage>90`
+//! means "some core is above 90 %".
+//! Numbers compare numerically; everything else only supports `==` / `!=`,
+//! case-insensitively (`battery_status==full`).
+
+use std::cmp::Ordering;
+
+use serde_json::Value;
+
+use crate::router::{ParsedPath, resolve_request};
+
+#[derive(Clone, Copy)]
+enum Op {
+    Ge,
+    Le,
+    Eq,
+    Ne,
+    Gt,
+    Lt,
+}
+
+impl Op {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Self::Ge => ordering.is_ge(),
+            Self::Le => ordering.is_le(),
+            Self::Eq => ordering.is_eq(),
+            Self::Ne => ordering.is_ne(),
+            Self::Gt => ordering.is_gt(),
+            Self::Lt => ordering.is_lt(),
+        }
+    }
+}
+
+/// Right-hand side of a comparison, kept both as typed text and as a number
+/// when it parses as one.
+struct Literal {
+    text: String,
+    number: Option<f64>,
+}
+
+/// A parsed condition, ready to be evaluated against any number of snapshots.
+pub struct Condition(Expr);
+
+enum Expr {
+    Compare { path: ParsedPath, op: Op, literal: Literal },
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Condition {
+    /// Parse a condition, returning a short description of the first error.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(Self(expr)),
+            Some(token) => Err(format!("unexpected {}", token.describe())),
+        }
+    }
+
+    /// Every path the condition reads, in order of appearance.
+    pub fn paths(&self) -> Vec<&ParsedPath> {
+        let mut paths = Vec::new();
+        self.0.paths(&mut paths);
+        paths
+    }
+
+    /// Evaluate against a value tree (a full snapshot or one array item).
+    pub fn eval(&self, tree: &Value) -> bool {
+        self.0.eval(tree)
+    }
+}
+
+impl Expr {
+    fn paths<'a>(&'a self, out: &mut Vec<&'a ParsedPath>) {
+        match self {
+            Self::Compare { path, .. } => out.push(path),
+            Self::Not(inner) => inner.paths(out),
+            Self::And(a, b) | Self::Or(a, b) => {
+                a.paths(out);
+                b.paths(out);
+            }
+        }
+    }
+
+    fn eval(&self, tree: &Value) -> bool {
+        match self {
+            Self::Compare { path, op, literal } => {
+                let Some(resolved) = resolve_request(tree, path) else {
+                    return false;
+                };
+                let mut values = Vec::new();
+                leaves(&resolved, &mut values);
+                values.iter().any(|value| compare(value, *op, literal))
+            }
+            Self::Not(inner) => !inner.eval(tree),
+            Self::And(a, b) => a.eval(tree) && b.eval(tree),
+            Self::Or(a, b) => a.eval(tree) || b.eval(tree),
+        }
+    }
+}
+
+/// Collect the scalar leaves of a resolved value.  `name` keys only identify
+/// array items, so they are skipped unless they are all there is.
+fn leaves<'a>(value: &'a Value, out: &mut Vec<&'a Value>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                if key != "name" || map.len() == 1 {
+                    leaves(child, out);
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| leaves(item, out)),
+        other => out.push(other),
+    }
+}
+
+fn compare(value: &Value, op: Op, literal: &Literal) -> bool {
+    if let (Some(a), Some(b)) = (value.as_f64(), literal.number) {
+        return a.partial_cmp(&b).is_some_and(|ordering| op.holds(ordering));
+    }
+
+    let text = match value {
+        Value::String(s) => s.clone(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        _ => return false,
+    };
+    match op {
+        Op::Eq => text.eq_ignore_ascii_case(&literal.text),
+        Op::Ne => !text.eq_ignore_ascii_case(&literal.text),
+        _ => false,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tokenizer
+// ---------------------------------------------------------------------------
+
+enum Token {
+    Open,
+    Close,
+    Not,
+    And,
+    Or,
+    Op(Op),
+    Word(String),
+    Quoted(String),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Self::Open => "'('".into(),
+            Self::Close => "')'".into(),
+            Self::Not => "'!'".into(),
+            Self::And => "'&&'".into(),
+            Self::Or => "'||'".into(),
+            Self::Op(_) => "operator".into(),
+            Self::Word(w) | Self::Quoted(w) => format!("'{w}'"),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        chars.next();
+        let token = match c {
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '&' | '|' if chars.next_if_eq(&c).is_none() => {
+                return Err(format!("expected '{c}{c}'"));
+            }
+            '&' => Token::And,
+            '|' => Token::Or,
+            '!' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Ne),
+            '!' => Token::Not,
+            '=' => {
+                // `=` and `==` are the same thing.
+                chars.next_if_eq(&'=');
+                Token::Op(Op::Eq)
+            }
+            '>' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Ge),
+            '>' => Token::Op(Op::Gt),
+            '<' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Le),
+            '<' => Token::Op(Op::Lt),
+            '"' | '\'' => {
+                let text: String = chars.by_ref().take_while(|&ch| ch != c).collect();
+                Token::Quoted(text)
+            }
+            _ => {
+                let mut word = String::from(c);
+                let mut depth = 0_usize;
+                while let Some(&ch) = chars.peek() {
+                    // A predicate (`cores[model_name=Cortex-X2]/usage`) is
+                    // part of the path, operators and all.
+                    match ch {
+                        '[' => depth += 1,
+                        ']' => depth = depth.saturating_sub(1),
+                        _ if depth > 0 => {}
+                        _ if ch.is_whitespace() || "()!&|=<>\"'".contains(ch) => break,
+                        _ => {}
+                    }
+                    word.push(ch);
+                    chars.next();
+                }
+                match word.as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Word(word),
+                }
+            }
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+// ---------------------------------------------------------------------------
+// Recursive-descent parser
+// ---------------------------------------------------------------------------
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, matches: fn(&Token) -> bool) -> bool {
+        let hit = self.tokens.get(self.pos).is_some_and(matches);
+        if hit {
+            self.pos += 1;
+        }
+        hit
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.and()?;
+        while self.eat(|t| matches!(t, Token::Or)) {
+            lhs = Expr::Or(Box::new(lhs), Box::new(self.and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        while self.eat(|t| matches!(t, Token::And)) {
+            lhs = Expr::And(Box::new(lhs), Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let inner = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(inner),
+                    _ => Err("expected ')'".into()),
+                }
+            }
+            Some(Token::Word(path)) => {
+                let path = path.clone();
+                let op = match self.next() {
+                    Some(Token::Op(op)) => *op,
+                    _ => return Err(format!("expected an operator after '{path}'")),
+                };
+                let literal = match self.next() {
+                    Some(Token::Word(text)) => {
+                        Literal { number: text.parse().ok(), text: text.clone() }
+                    }
+                    Some(Token::Quoted(text)) => Literal { text: text.clone(), number: None },
+                    _ => return Err(format!("expected a value after '{path}'")),
+                };
+                Ok(Expr::Compare { path: ParsedPath::parse(&path)?, op, literal })
+            }
+            Some(token) => Err(format!("unexpected {}", token.describe())),
+            None => Err("unexpected end of condition".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn holds(input: &str, tree: &Value) -> bool {
+        match Condition::parse(input) {
+            Ok(cond) => cond.eval(tree),
+            Err(e) => panic!("{input}: {e}"),
+        }
+    }
+
+    fn error(input: &str) -> String {
+        match Condition::parse(input) {
+            Ok(_) => panic!("{input} parsed"),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let tree = json!({ "a": 1, "b": 0, "c": 0 });
+        assert!(holds("a==1 or b==1 and c==1", &tree));
+        assert!(holds("b==1 and c==1 or a==1", &tree));
+        assert!(holds("a==1 || b==1 && c==1", &tree));
+        assert!(!holds("(a==1 or b==1) and c==1", &tree));
+        assert!(!holds("not (a==1 or b==1)", &tree));
+        assert!(holds("not a==0 and !(b!=0)", &tree));
+    }
+
+    #[test]
+    fn numbers_compare_numerically() {
+        let tree = json!({ "battery_level": 80, "cpu_temp": 41.5 });
+        assert!(holds("battery_level>=80", &tree));
+        assert!(!holds("battery_level>80", &tree));
+        assert!(holds("battery_level=80.0", &tree));
+        assert!(holds("cpu_temp<41.6 and cpu_temp>41", &tree));
+        assert!(holds("cpu_temp!=9", &tree));
+    }
+
+    #[test]
+    fn strings_compare_case_insensitively_and_only_for_equality() {
+        let tree = json!({ "battery_status": "Charging", "status": "Light warm" });
+        assert!(holds("battery_status==charging", &tree));
+        assert!(holds("battery_status!=FULL", &tree));
+        assert!(!holds("battery_status>charging", &tree));
+        assert!(holds("status==\"light WARM\"", &tree));
+        assert!(holds("status=='Light warm'", &tree));
+        // A quoted literal is text even when it looks like a number.
+        assert!(!holds("battery_status<'5'", &tree));
+    }
+
+    #[test]
+    fn a_wildcard_holds_if_any_value_does() {
+        let tree = json!({
+            "cores": [
+                { "name": "cpu0", "usage": 10.0 },
+                { "name": "cpu4", "usage": 95.0 },
+            ],
+        });
+        assert!(holds("cores/*/usage>90", &tree));
+        assert!(holds("cores/*/usage<20", &tree));
+        assert!(!holds("cores/*/usage>99", &tree));
+        assert!(holds("cores[usage>50]/usage>90", &tree));
+        // Item names identify items; they aren't values of the path.
+        assert!(!holds("cores/*/usage==cpu0", &tree));
+        assert!(holds("cores/cpu0/name==CPU0", &tree));
+    }
+
+    #[test]
+    fn a_path_that_resolves_to_nothing_never_holds() {
+        let tree = json!({ "battery_level": 80 });
+        let cond = Condition::parse("nope>1 or battery_level>90").unwrap_or_else(|e| panic!("{e}"));
+        assert!(!cond.eval(&tree));
+        let paths: Vec<&str> = cond.paths().into_iter().map(ParsedPath::as_str).collect();
+        assert_eq!(paths, ["nope", "battery_level"]);
+    }
+
+    #[test]
+    fn errors_name_the_first_problem() {
+        assert_eq!(error("battery_level>="), "expected a value after 'battery_level'");
+        assert_eq!(error("battery_level"), "expected an operator after 'battery_level'");
+        assert_eq!(error("battery_level 80"), "expected an operator after 'battery_level'");
+        assert_eq!(error("battery_level>=80 and"), "unexpected end of condition");
+        assert_eq!(error("and battery_level>=80"), "unexpected '&&'");
+        assert_eq!(error("battery_level>=80 or or a==1"), "unexpected '||'");
+        assert_eq!(error("(battery_level>=80"), "expected ')'");
+        assert_eq!(error("battery_level>=80)"), "unexpected ')'");
+        assert_eq!(error("a==1 & b==1"), "expected '&&'");
+        assert_eq!(error(""), "unexpected end of condition");
+        assert!(error("cores[usage>]/usage>1").starts_with("invalid predicate: "));
+    }
+}
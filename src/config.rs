@@ -0,0 +1,372 @@
+//! Optional user configuration, loaded once at startup.
+//!
+//! Read from `$ASMO_CONFIG`, falling back to `~/.config/asmo/config.toml`.
+//! A missing file is not an error — every field has a sensible default.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer};
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct Config {
+    pub storage: StorageConfig,
+    pub history: HistoryConfig,
+    pub persist: PersistConfig,
+    pub influx: InfluxConfig,
+    pub mqtt: MqttConfig,
+    pub statsd: StatsdConfig,
+    pub graphite: GraphiteConfig,
+    pub otlp: OtlpConfig,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    /// Mount points reported under `storage`, in order.
+    pub mounts: Vec<String>,
+    /// Also report removable volumes (SD cards, USB drives) found in `/proc/mounts`.
+    pub discover_external: bool,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            mounts: vec!["/data".to_owned(), "/storage/emulated/0".to_owned()],
+            discover_external: true,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// How far back the in-memory ring buffer reaches.
+    #[serde(deserialize_with = "duration")]
+    pub retention: Duration,
+    /// Minimum spacing between retained snapshots.
+    #[serde(deserialize_with = "duration")]
+    pub interval: Duration,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            retention: Duration::from_secs(3600),
+            interval: Duration::from_millis(500),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct PersistConfig {
+    /// Write history to disk so it survives restarts.
+    pub enabled: bool,
+    /// Defaults to `~/.local/share/asmo`.
+    pub dir: Option<PathBuf>,
+    /// How long every raw snapshot is kept.
+    #[serde(deserialize_with = "duration")]
+    pub raw_retention: Duration,
+    /// Bucket size of the downsampled (averaged) tier.
+    #[serde(deserialize_with = "duration")]
+    pub rollup_interval: Duration,
+    /// How long downsampled buckets are kept.
+    #[serde(deserialize_with = "duration")]
+    pub rollup_retention: Duration,
+    /// How often buffered snapshots are written out.
+    #[serde(deserialize_with = "duration")]
+    pub flush_interval: Duration,
+}
+
+impl Default for PersistConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: None,
+            raw_retention: Duration::from_secs(86_400),
+            rollup_interval: Duration::from_secs(60),
+            rollup_retention: Duration::from_secs(30 * 86_400),
+            flush_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+impl PersistConfig {
+    pub fn dir(&self) -> PathBuf {
+        self.dir.clone().unwrap_or_else(data_dir)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct InfluxConfig {
+    /// Push snapshots to InfluxDB.
+    pub enabled: bool,
+    /// `http(s)://host:8086` for the v2 write API, or `udp://host:8089`.
+    pub url: String,
+    /// API token, sent as `Authorization: Token …` (HTTP only).
+    pub token: Option<String>,
+    pub org: String,
+    pub bucket: String,
+    /// Spacing between exported snapshots.
+    #[serde(deserialize_with = "duration")]
+    pub interval: Duration,
+    /// How often buffered lines are written out.
+    #[serde(deserialize_with = "duration")]
+    pub flush_interval: Duration,
+    /// Write out early once this many lines are buffered.
+    pub batch_size: usize,
+    /// Where batches wait while the target is unreachable.
+    /// Defaults to `~/.local/share/asmo/influx-spool`.
+    pub spool_dir: Option<PathBuf>,
+    /// Oldest spooled batches are dropped beyond this size.
+    pub spool_max_mb: u64,
+}
+
+impl Default for InfluxConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: "http://localhost:8086".to_owned(),
+            token: None,
+            org: String::new(),
+            bucket: "asmo".to_owned(),
+            interval: Duration::from_secs(5),
+            flush_interval: Duration::from_secs(10),
+            batch_size: 5000,
+            spool_dir: None,
+            spool_max_mb: 64,
+        }
+    }
+}
+
+impl InfluxConfig {
+    pub fn spool_dir(&self) -> PathBuf {
+        self.spool_dir.clone().unwrap_or_else(|| data_dir().join("influx-spool"))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    /// Publish snapshots to an MQTT broker.
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Identifies this phone in topics.  Defaults to the device codename.
+    pub device_id: Option<String>,
+    /// Topics are `<topic_prefix>/<device_id>/<path>`.
+    pub topic_prefix: String,
+    /// How often changed values are published.
+    #[serde(deserialize_with = "duration")]
+    pub interval: Duration,
+    /// Publish values as retained messages.
+    pub retain: bool,
+    /// Announce every field to Home Assistant via MQTT discovery.
+    pub discovery: bool,
+    pub discovery_prefix: String,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "localhost".to_owned(),
+            port: 1883,
+            username: None,
+            password: None,
+            device_id: None,
+            topic_prefix: "asmo".to_owned(),
+            interval: Duration::from_secs(5),
+            retain: true,
+            discovery: true,
+            discovery_prefix: "homeassistant".to_owned(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct StatsdConfig {
+    /// Send snapshots to a StatsD daemon as gauges.
+    pub enabled: bool,
+    /// `host:port` of the daemon, resolved again on every send.
+    pub address: String,
+    /// Metric name prefix.  `{manufacturer}`, `{product_model}`, `{soc_model}`
+    /// and `{codename}` are replaced by the device's identity.
+    pub prefix: String,
+    /// Router paths to export (`cpu_temp`, `cores/*/usage`); empty for all.
+    pub paths: Vec<String>,
+    /// Spacing between exported snapshots.
+    #[serde(deserialize_with = "duration")]
+    pub interval: Duration,
+}
+
+impl Default for StatsdConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "localhost:8125".to_owned(),
+            prefix: "asmo.{product_model}".to_owned(),
+            paths: Vec::new(),
+            interval: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct GraphiteConfig {
+    /// Send snapshots to Graphite over the plaintext protocol.
+    pub enabled: bool,
+    /// `host:port` of the Carbon plaintext listener.
+    pub address: String,
+    /// Metric name prefix, with the same placeholders as `[statsd]`.
+    pub prefix: String,
+    /// Router paths to export; empty for all.
+    pub paths: Vec<String>,
+    /// Spacing between exported snapshots.
+    #[serde(deserialize_with = "duration")]
+    pub interval: Duration,
+}
+
+impl Default for GraphiteConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "localhost:2003".to_owned(),
+            prefix: "asmo.{product_model}".to_owned(),
+            paths: Vec::new(),
+            interval: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct OtlpConfig {
+    /// Send snapshots to an OpenTelemetry collector over OTLP/HTTP.
+    pub enabled: bool,
+    /// Collector base URL; `/v1/metrics` is appended unless already there.
+    pub endpoint: String,
+    /// Extra request headers, e.g. `Authorization`.
+    pub headers: HashMap<String, String>,
+    /// `service.name` resource attribute.
+    pub service_name: String,
+    /// Spacing between exported snapshots.
+    #[serde(deserialize_with = "duration")]
+    pub interval: Duration,
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: "http://localhost:4318".to_owned(),
+            headers: HashMap::new(),
+            service_name: "asmo".to_owned(),
+            interval: Duration::from_secs(10),
+        }
+    }
+}
+
+impl OtlpConfig {
+    pub fn metrics_url(&self) -> String {
+        let base = self.endpoint.trim_end_matches('/');
+        if base.ends_with("/v1/metrics") { base.to_owned() } else { format!("{base}/v1/metrics") }
+    }
+}
+
+/// `~/.local/share/asmo`, where on-disk state lives unless configured otherwise.
+fn data_dir() -> PathBuf {
+    let home = std::env::var_os("HOME").unwrap_or_else(|| ".".into());
+    PathBuf::from(home).join(".local/share/asmo")
+}
+
+// ---------------------------------------------------------------------------
+// Durations — `500ms`, `10s`, `5m`, `1h`, `30d`
+// ---------------------------------------------------------------------------
+
+/// Parse a human duration: an integer followed by `ms`, `s`, `m`, `h` or `d`.
+/// A bare number is taken as seconds.  `None` if malformed, or too long to
+/// count in milliseconds.
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+    let n: u64 = num.parse().ok()?;
+
+    let scale: u64 = match unit {
+        "ms" => 1,
+        "" | "s" => 1000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        "d" => 86_400_000,
+        _ => return None,
+    };
+    Some(Duration::from_millis(n.checked_mul(scale)?))
+}
+
+fn duration<'de, D: Deserializer<'de>>(de: D) -> Result<Duration, D::Error> {
+    let raw = String::deserialize(de)?;
+    parse_duration(&raw)
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid duration `{raw}`")))
+}
+
+// ---------------------------------------------------------------------------
+// Loading
+// ---------------------------------------------------------------------------
+
+pub fn load() -> Config {
+    let Some(path) = config_path() else { return Config::default() };
+    let Ok(raw) = std::fs::read_to_string(&path) else { return Config::default() };
+
+    toml::from_str(&raw)
+        .unwrap_or_else(|e| panic!("invalid config {}: {e}", path.display()))
+}
+
+fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("ASMO_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let home = std::env::var_os("HOME")?;
+    Some(PathBuf::from(home).join(".config/asmo/config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_reads_every_unit() {
+        assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_duration("10s"), Some(Duration::from_secs(10)));
+        assert_eq!(parse_duration("10"), Some(Duration::from_secs(10)));
+        assert_eq!(parse_duration("5m"), Some(Duration::from_secs(300)));
+        assert_eq!(parse_duration("2h"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("30d"), Some(Duration::from_secs(30 * 86_400)));
+        assert_eq!(parse_duration(" 1s "), Some(Duration::from_secs(1)));
+        assert_eq!(parse_duration("0s"), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn parse_duration_rejects_malformed_input() {
+        for raw in ["", "  ", "s", "ms", "-5s", "+5s", "1.5s", "5 m", "5w", "5sec", "1h30m"] {
+            assert_eq!(parse_duration(raw), None, "{raw:?}");
+        }
+    }
+
+    #[test]
+    fn parse_duration_rejects_what_overflows_milliseconds() {
+        let max = u64::MAX;
+        assert_eq!(parse_duration(&format!("{max}ms")), Some(Duration::from_millis(max)));
+        assert_eq!(parse_duration(&format!("{max}s")), None);
+        assert_eq!(parse_duration(&format!("{}d", max / 86_400_000 + 1)), None);
+        assert_eq!(parse_duration("18446744073709551616ms"), None);
+    }
+}
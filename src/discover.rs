@@ -1,22 +1,34 @@
 use std::fs;
 use std::process::Command;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::types::{DevicePaths, StaticCoreInfo, StaticDeviceInfo};
+use crate::clock::read_clock;
+use crate::config::Config;
+use crate::types::{
+    DeviceInfo, DevicePaths, StaticClusterInfo, StaticCoreInfo, StaticDeviceInfo, StorageMount,
+};
 
 // ---------------------------------------------------------------------------
 // One-shot device discovery — runs at startup, never again.
 // ---------------------------------------------------------------------------
 
-pub fn discover_device_layout() -> (DevicePaths, StaticDeviceInfo) {
+pub fn discover_device_layout(config: &Config) -> (DevicePaths, StaticDeviceInfo) {
     let (cpu_temp, gpu_temp, core_count) = probe_thermal_and_cores();
+    let storage = probe_storage_mounts(config);
+    let block_devices = probe_block_devices();
     let (manufacturer, product_model, soc_model) = probe_device_props();
     let (kernel_version, android_version) = probe_system_versions();
+    let device = probe_device_info();
     let cores = probe_core_info(core_count);
+    let clusters = probe_clusters();
+    let boot_time = probe_boot_time();
 
     let paths = DevicePaths {
         cpu_temp: cpu_temp.into_boxed_str(),
         gpu_temp: gpu_temp.into_boxed_str(),
+        storage: storage.into_boxed_slice(),
+        block_devices: block_devices.into_boxed_slice(),
     };
 
     let static_info = StaticDeviceInfo {
@@ -25,7 +37,10 @@ pub fn discover_device_layout() -> (DevicePaths, StaticDeviceInfo) {
         soc_model: Arc::from(soc_model),
         kernel_version: Arc::from(kernel_version),
         android_version: Arc::from(android_version),
+        device: Arc::new(device),
+        boot_time,
         cores: cores.into_boxed_slice(),
+        clusters: clusters.into_boxed_slice(),
     };
 
     (paths, static_info)
@@ -81,24 +96,202 @@ fn probe_thermal_and_cores() -> (String, String, usize) {
     (cpu_temp, gpu_temp, core_count)
 }
 
-/// Read device identity via Android `getprop`.
-fn probe_device_props() -> (String, String, String) {
-    let get = |key| -> String {
-        Command::new("getprop")
-            .arg(key)
-            .output()
-            .ok()
-            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_owned())
-            .unwrap_or_default()
+/// Resolve the configured mount points, plus removable volumes from `/proc/mounts`.
+fn probe_storage_mounts(config: &Config) -> Vec<StorageMount> {
+    let mut mounts: Vec<String> = config.storage.mounts.clone();
+
+    // Removable volumes are mounted as `/storage/XXXX-XXXX`; the emulated
+    // internal storage and the per-user `self` alias are skipped.
+    if config.storage.discover_external
+        && let Ok(content) = fs::read_to_string("/proc/mounts")
+    {
+        for line in content.lines() {
+            let Some(point) = line.split_whitespace().nth(1) else { continue };
+            let Some(volume) = point.strip_prefix("/storage/") else { continue };
+            if volume.is_empty()
+                || volume.contains('/')
+                || volume == "emulated"
+                || volume == "self"
+            {
+                continue;
+            }
+            if !mounts.iter().any(|m| m == point) {
+                mounts.push(point.to_owned());
+            }
+        }
+    }
+
+    let mut names: Vec<String> = Vec::with_capacity(mounts.len());
+    for point in &mounts {
+        let name = unique_storage_name(point, &names);
+        names.push(name);
+    }
+
+    names
+        .into_iter()
+        .zip(mounts)
+        .map(|(name, point)| StorageMount {
+            name: Arc::from(name.as_str()),
+            mount_point: Arc::from(point.as_str()),
+        })
+        .collect()
+}
+
+/// Short, URL-friendly name for a mount point: `/data` → `data`,
+/// `/storage/emulated/0` → `sdcard` (Android's own alias), otherwise the
+/// last path component (e.g. the `1234-ABCD` volume id of an SD card).
+fn storage_name(point: &str) -> &str {
+    match point {
+        "/storage/emulated/0" | "/sdcard" => "sdcard",
+        "/" => "root",
+        _ => point.trim_end_matches('/').rsplit('/').next().unwrap_or(point),
+    }
+}
+
+/// [`storage_name`], unless an earlier mount in `taken` already has it.
+/// Items are addressed by name, so a clash is named after its whole mount
+/// point instead (`/mnt/media/data` → `mnt_media_data`), numbered if even
+/// that is taken.
+fn unique_storage_name(point: &str, taken: &[String]) -> String {
+    let short = storage_name(point);
+    if !taken.iter().any(|name| name == short) {
+        return short.to_owned();
+    }
+
+    let long = match point.trim_matches('/') {
+        "" => short.to_owned(),
+        trimmed => trimmed.replace('/', "_"),
     };
+    let mut name = long.clone();
+    let mut n = 2;
+    while taken.contains(&name) {
+        name = format!("{long}_{n}");
+        n += 1;
+    }
+    name
+}
 
+/// List whole block devices from sysfs, skipping virtual ones
+/// (loop, ram, zram and device-mapper).
+fn probe_block_devices() -> Vec<Arc<str>> {
+    let Ok(entries) = fs::read_dir("/sys/block") else { return Vec::new() };
+
+    let mut devices: Vec<String> = entries
+        .filter_map(Result::ok)
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .filter(|name| {
+            !["loop", "ram", "zram", "dm-"].iter().any(|p| name.starts_with(p))
+        })
+        .collect();
+    devices.sort_unstable();
+
+    devices.into_iter().map(|d| Arc::from(d.as_str())).collect()
+}
+
+/// Read a single Android system property, empty if unset.
+fn getprop(key: &str) -> String {
+    Command::new("getprop")
+        .arg(key)
+        .output()
+        .ok()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_owned())
+        .unwrap_or_default()
+}
+
+/// Read device identity via Android `getprop`.
+fn probe_device_props() -> (String, String, String) {
     (
-        get("ro.product.manufacturer"),
-        get("ro.product.model"),
-        get("ro.soc.model"),
+        getprop("ro.product.manufacturer"),
+        getprop("ro.product.model"),
+        getprop("ro.soc.model"),
     )
 }
 
+/// Gather extended identity and build info for the `device` object.
+///
+/// Properties come from `getprop`; screen metrics and SELinux mode need shell
+/// privileges, so they are fetched with a single one-shot `rish` call.
+fn probe_device_info() -> DeviceInfo {
+    let mut info = DeviceInfo {
+        brand: getprop("ro.product.brand").into(),
+        codename: getprop("ro.product.device").into(),
+        fingerprint: getprop("ro.build.fingerprint").into(),
+        security_patch: getprop("ro.build.version.security_patch").into(),
+        sdk_int: getprop("ro.build.version.sdk").parse().unwrap_or(0),
+        abis: getprop("ro.product.cpu.abilist")
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(Into::into)
+            .collect(),
+        bootloader: getprop("ro.bootloader").into(),
+        baseband: getprop("gsm.version.baseband").into(),
+        ram_total_mb: probe_ram_total_mb(),
+        kernel_compiler: probe_kernel_compiler().into(),
+        ..DeviceInfo::default()
+    };
+
+    let output = Command::new("rish")
+        .args(["-c", "wm size; wm density; getenforce"])
+        .output()
+        .ok()
+        .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+        .unwrap_or_default();
+
+    for line in output.lines().map(str::trim) {
+        if let Some(size) = line.strip_prefix("Physical size:") {
+            let (w, h) = size.trim().split_once('x').unwrap_or(("0", "0"));
+            info.screen_width = w.parse().unwrap_or(0);
+            info.screen_height = h.parse().unwrap_or(0);
+        } else if let Some(density) = line.strip_prefix("Physical density:") {
+            info.screen_density = density.trim().parse().unwrap_or(0);
+        } else if matches!(line, "Enforcing" | "Permissive" | "Disabled") {
+            info.selinux = line.into();
+        }
+    }
+
+    info
+}
+
+/// Read `MemTotal` from `/proc/meminfo`, in MB.
+fn probe_ram_total_mb() -> f32 {
+    fs::read_to_string("/proc/meminfo")
+        .unwrap_or_default()
+        .lines()
+        .find_map(|l| l.strip_prefix("MemTotal:"))
+        .and_then(|rest| rest.split_whitespace().next()?.parse::<f32>().ok())
+        .unwrap_or(0.0)
+        / 1024.0
+}
+
+/// Extract the toolchain from `/proc/version` — the second parenthesised
+/// group, e.g. `Android (7284624, based on r416183b) clang version 12.0.5 …`.
+fn probe_kernel_compiler() -> String {
+    let version = fs::read_to_string("/proc/version").unwrap_or_default();
+
+    let mut groups = Vec::new();
+    let mut depth = 0_usize;
+    let mut start = 0_usize;
+    for (i, c) in version.char_indices() {
+        match c {
+            '(' => {
+                if depth == 0 {
+                    start = i + 1;
+                }
+                depth += 1;
+            }
+            ')' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    groups.push(&version[start..i]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    groups.get(1).map(|g| g.trim().to_owned()).unwrap_or_default()
+}
+
 /// Read kernel and Android version (static, called once at startup).
 fn probe_system_versions() -> (String, String) {
     let kernel_version = Command::new("uname")
@@ -108,16 +301,20 @@ fn probe_system_versions() -> (String, String) {
         .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_owned())
         .unwrap_or_else(|| "unknown".to_owned());
 
-    let android_version = Command::new("getprop")
-        .arg("ro.build.version.release")
-        .output()
-        .ok()
-        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_owned())
-        .unwrap_or_default();
+    let android_version = getprop("ro.build.version.release");
 
     (kernel_version, android_version)
 }
 
+/// Unix timestamp (seconds) of the last boot: wall clock minus `CLOCK_BOOTTIME`.
+fn probe_boot_time() -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0);
+    (now - read_clock(libc::CLOCK_BOOTTIME)).max(0.0) as u64
+}
+
 /// Gather static per-core info from `lscpu`.
 fn probe_core_info(hint: usize) -> Vec<StaticCoreInfo> {
     let output = Command::new("lscpu")
@@ -145,6 +342,7 @@ fn probe_core_info(hint: usize) -> Vec<StaticCoreInfo> {
             model_name: Arc::from(model_name.as_str()),
             min_freq,
             max_freq,
+            idle_states: probe_idle_states(cpu_str),
         });
     }
 
@@ -154,4 +352,58 @@ fn probe_core_info(hint: usize) -> Vec<StaticCoreInfo> {
     });
 
     cores
-}
\ No newline at end of file
+}
+
+/// Names of a core's cpuidle states, in `stateN` order (e.g. `WFI`, `C2`).
+fn probe_idle_states(cpu: &str) -> Box<[Arc<str>]> {
+    let dir = format!("/sys/devices/system/cpu/cpu{cpu}/cpuidle");
+    (0..)
+        .map_while(|i| fs::read_to_string(format!("{dir}/state{i}/name")).ok())
+        .map(|name| Arc::from(name.trim()))
+        .collect()
+}
+
+/// Enumerate cpufreq policies and the cores each one clocks.
+fn probe_clusters() -> Vec<StaticClusterInfo> {
+    let Ok(entries) = fs::read_dir("/sys/devices/system/cpu/cpufreq") else {
+        return Vec::new();
+    };
+
+    let mut clusters: Vec<StaticClusterInfo> = entries
+        .filter_map(Result::ok)
+        .filter(|e| e.file_name().to_string_lossy().starts_with("policy"))
+        .map(|e| {
+            let related = fs::read_to_string(e.path().join("related_cpus")).unwrap_or_default();
+            StaticClusterInfo {
+                name: Arc::from(e.file_name().to_string_lossy().as_ref()),
+                cores: related
+                    .split_whitespace()
+                    .map(|n| Arc::from(format!("cpu{n}").as_str()))
+                    .collect(),
+            }
+        })
+        .collect();
+
+    clusters.sort_unstable_by_key(|c| {
+        c.name.get(6..).and_then(|n| n.parse::<usize>().ok()).unwrap_or(0)
+    });
+
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_names_are_unique() {
+        let mounts = ["/data", "/storage/emulated/0", "/sdcard", "/mnt/media/data", "/sdcard/"];
+        let mut names: Vec<String> = Vec::new();
+        for point in mounts {
+            let name = unique_storage_name(point, &names);
+            names.push(name);
+        }
+
+        assert_eq!(names, ["data", "sdcard", "sdcard_2", "mnt_media_data", "sdcard_3"]);
+    }
+}
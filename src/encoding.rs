@@ -0,0 +1,378 @@
+//! Response encodings for the JSON endpoints, chosen per request.
+//!
+//! `?format=` wins over `Accept`; with neither, the answer is JSON.
+//!
+//! | `format`  | Media type            | Shape                                        |
+//! |-----------|-----------------------|----------------------------------------------|
+//! | `json`    | `application/json`    | As documented everywhere else                |
+//! | `csv`     | `text/csv`            | One row per array item, or a single row      |
+//! | `msgpack` | `application/msgpack` | The JSON tree, binary                        |
+//! | `cbor`    | `application/cbor`    | The JSON tree, binary                        |
+//! | `text`    | `text/plain`          | A single value bare, otherwise `path=value`  |
+//!
+//! CSV and text flatten nested values into dotted columns, naming array items
+//! the way the router does: `cores.cpu0.usage`.
+
+use axum::Json;
+use axum::extract::{FromRequestParts, Query};
+use axum::http::header::{ACCEPT, CONTENT_TYPE, VARY};
+use axum::http::StatusCode;
+use axum::http::request::Parts;
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::router::error_response;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Json,
+    Csv,
+    MsgPack,
+    Cbor,
+    Text,
+}
+
+impl Encoding {
+    /// `?format=` names.
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            "msgpack" | "messagepack" => Some(Self::MsgPack),
+            "cbor" => Some(Self::Cbor),
+            "text" | "txt" | "plain" => Some(Self::Text),
+            _ => None,
+        }
+    }
+
+    fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type.to_ascii_lowercase().as_str() {
+            "application/json" | "application/*" | "*/*" => Some(Self::Json),
+            "text/csv" => Some(Self::Csv),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+                Some(Self::MsgPack)
+            }
+            "application/cbor" => Some(Self::Cbor),
+            "text/plain" | "text/*" => Some(Self::Text),
+            _ => None,
+        }
+    }
+
+    /// The most preferred supported media range of an `Accept` header, the
+    /// first listed on ties; `None` if nothing listed is supported.
+    fn from_accept(accept: &str) -> Option<Self> {
+        let mut best: Option<(f32, Self)> = None;
+        for range in accept.split(',') {
+            let mut params = range.split(';').map(str::trim);
+            let Some(encoding) = params.next().and_then(Self::from_media_type) else { continue };
+            let q = params
+                .find_map(|p| p.strip_prefix("q="))
+                .and_then(|q| q.parse().ok())
+                .unwrap_or(1.0);
+            if q > 0.0 && best.is_none_or(|(best_q, _)| q > best_q) {
+                best = Some((q, encoding));
+            }
+        }
+        best.map(|(_, encoding)| encoding)
+    }
+
+    /// Canonical `?format=` name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::MsgPack => "msgpack",
+            Self::Cbor => "cbor",
+            Self::Text => "text",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::MsgPack => "application/msgpack",
+            Self::Cbor => "application/cbor",
+            Self::Text => "text/plain; charset=utf-8",
+        }
+    }
+
+    /// `200 OK` with `value` in this encoding.
+    pub fn respond(self, value: &Value) -> Response {
+        let body = match self {
+            Self::Json => return ([(VARY, "Accept")], Json(value)).into_response(),
+            Self::Csv => to_csv(value).into_bytes(),
+            Self::Text => to_text(value).into_bytes(),
+            Self::MsgPack => match rmp_serde::to_vec_named(value) {
+                Ok(bytes) => bytes,
+                Err(e) => return encode_error(e),
+            },
+            Self::Cbor => {
+                let mut bytes = Vec::new();
+                if let Err(e) = ciborium::into_writer(value, &mut bytes) {
+                    return encode_error(e);
+                }
+                bytes
+            }
+        };
+        ([(CONTENT_TYPE, self.content_type()), (VARY, "Accept")], body).into_response()
+    }
+}
+
+fn encode_error(e: impl std::fmt::Display) -> Response {
+    (StatusCode::INTERNAL_SERVER_ERROR, format!("encoding failed: {e}")).into_response()
+}
+
+#[derive(Deserialize)]
+struct FormatParam {
+    format: Option<String>,
+}
+
+/// Extracted from `?format=` or else `Accept`.  An unknown `format` is
+/// `400`; an `Accept` listing nothing supported is `406`.
+#[axum::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Encoding {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let path = parts.uri.path().trim_start_matches('/');
+        let format = Query::<FormatParam>::try_from_uri(&parts.uri)
+            .ok()
+            .and_then(|Query(params)| params.format);
+
+        if let Some(name) = format {
+            return Self::from_name(&name).ok_or_else(|| {
+                error_response(StatusCode::BAD_REQUEST, &format!("unknown format `{name}`"), path)
+            });
+        }
+        match parts.headers.get(ACCEPT).and_then(|v| v.to_str().ok()) {
+            None => Ok(Self::Json),
+            Some(accept) => Self::from_accept(accept).ok_or_else(|| {
+                error_response(StatusCode::NOT_ACCEPTABLE, "no acceptable format", path)
+            }),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Flattening — dotted columns for CSV and text
+// ---------------------------------------------------------------------------
+
+/// Flatten `value` into `(column, scalar)` pairs.  Array items are named by
+/// their `name` (which is then not a column of its own), or else by index.
+fn flatten(value: &Value, prefix: &str, out: &mut Vec<(String, String)>) {
+    let join = |key: &str| match prefix {
+        "" => key.to_owned(),
+        prefix => format!("{prefix}.{key}"),
+    };
+
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                flatten(child, &join(key), out);
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                match item.get("name").and_then(Value::as_str) {
+                    Some(name) => {
+                        let item_prefix = join(name);
+                        let fields = item.as_object().into_iter().flatten();
+                        for (key, child) in fields.filter(|(k, _)| k.as_str() != "name") {
+                            flatten(child, &format!("{item_prefix}.{key}"), out);
+                        }
+                    }
+                    None => flatten(item, &join(&i.to_string()), out),
+                }
+            }
+        }
+        scalar => out.push((prefix.to_owned(), scalar_text(scalar))),
+    }
+}
+
+/// A scalar as bare text.  Floats are printed at `f32` precision, which is
+/// what every float in [`SystemStats`](crate::types::SystemStats) is.
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) if n.is_f64() => (n.as_f64().unwrap_or_default() as f32).to_string(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Drop single-key wrappers (`{"battery_level": 100}`) down to what they hold.
+fn unwrap(mut value: &Value) -> &Value {
+    while let Value::Object(map) = value
+        && map.len() == 1
+        && let Some(inner) = map.values().next()
+    {
+        value = inner;
+    }
+    value
+}
+
+/// A bare value for a single field, otherwise one `path=value` line each.
+fn to_text(value: &Value) -> String {
+    match unwrap(value) {
+        Value::Object(_) | Value::Array(_) => {
+            let mut pairs = Vec::new();
+            flatten(value, "", &mut pairs);
+            pairs.into_iter().map(|(key, value)| format!("{key}={value}\n")).collect()
+        }
+        scalar => format!("{}\n", scalar_text(scalar)),
+    }
+}
+
+/// One row per array item (`/cores/all/usage,cur_freq`), or a single row for
+/// anything else.  Columns are the union of every row's, in first-seen order.
+fn to_csv(value: &Value) -> String {
+    let rows: Vec<Vec<(String, String)>> = match unwrap(value) {
+        Value::Array(items) if items.iter().all(Value::is_object) => items
+            .iter()
+            .map(|item| {
+                let mut row = Vec::new();
+                flatten(item, "", &mut row);
+                row
+            })
+            .collect(),
+        _ => {
+            let mut row = Vec::new();
+            flatten(value, "", &mut row);
+            vec![row]
+        }
+    };
+
+    let mut columns: Vec<&str> = Vec::new();
+    for (column, _) in rows.iter().flatten() {
+        if !columns.contains(&column.as_str()) {
+            columns.push(column);
+        }
+    }
+
+    let mut out = String::new();
+    push_record(&mut out, columns.iter().copied());
+    for row in &rows {
+        push_record(
+            &mut out,
+            columns.iter().map(|column| {
+                row.iter().find(|(c, _)| c == column).map_or("", |(_, v)| v.as_str())
+            }),
+        );
+    }
+    out
+}
+
+fn push_record<'a>(out: &mut String, cells: impl Iterator<Item = &'a str>) {
+    for (i, cell) in cells.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        if cell.contains([',', '"', '\n', '\r']) {
+            out.push('"');
+            out.push_str(&cell.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(cell);
+        }
+    }
+    out.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Request;
+    use serde_json::json;
+
+    use super::*;
+
+    async fn negotiate(uri: &str, accept: Option<&str>) -> Result<Encoding, StatusCode> {
+        let mut request = Request::builder().uri(uri);
+        if let Some(accept) = accept {
+            request = request.header(ACCEPT, accept);
+        }
+        let (mut parts, ()) = request.body(()).unwrap().into_parts();
+        Encoding::from_request_parts(&mut parts, &()).await.map_err(|r| r.status())
+    }
+
+    #[test]
+    fn csv_quotes_cells_that_need_it() {
+        let value = json!({ "a": "plain", "b": "x,y", "c": "say \"hi\"", "d": "two\nlines" });
+        assert_eq!(
+            to_csv(&value),
+            "a,b,c,d\r\nplain,\"x,y\",\"say \"\"hi\"\"\",\"two\nlines\"\r\n"
+        );
+    }
+
+    #[test]
+    fn csv_has_a_row_per_item_and_the_union_of_columns() {
+        let value = json!({
+            "cores": [
+                { "name": "cpu0", "usage": 12.5 },
+                { "name": "cpu4", "usage": 40.0, "cur_freq": 2400 },
+            ],
+        });
+        assert_eq!(to_csv(&value), "name,usage,cur_freq\r\ncpu0,12.5,\r\ncpu4,40,2400\r\n");
+    }
+
+    #[test]
+    fn nested_arrays_flatten_into_dotted_columns() {
+        let value = json!({
+            "cores": [{
+                "name": "cpu0",
+                "idle_states": [{ "name": "WFI", "usage": 3 }, { "usage": 4 }],
+                "online": true,
+                "governor": null,
+            }],
+        });
+        assert_eq!(
+            to_csv(&value),
+            "name,idle_states.WFI.usage,idle_states.1.usage,online,governor\r\n\
+             cpu0,3,4,true,\r\n"
+        );
+        assert_eq!(
+            to_text(&value),
+            "cores.cpu0.idle_states.WFI.usage=3\n\
+             cores.cpu0.idle_states.1.usage=4\n\
+             cores.cpu0.online=true\n\
+             cores.cpu0.governor=\n"
+        );
+    }
+
+    #[test]
+    fn text_is_bare_for_a_single_value() {
+        assert_eq!(to_text(&json!({ "battery_level": 81 })), "81\n");
+        assert_eq!(to_text(&json!({ "cpu_temp": 41.3_f32 })), "41.3\n");
+        assert_eq!(to_text(&json!({ "a": 1, "b": "x" })), "a=1\nb=x\n");
+    }
+
+    #[test]
+    fn accept_picks_the_most_preferred_supported_type() {
+        let accept = Encoding::from_accept;
+        assert_eq!(accept("text/csv"), Some(Encoding::Csv));
+        assert_eq!(accept("text/html, application/cbor"), Some(Encoding::Cbor));
+        assert_eq!(accept("application/json;q=0.5, text/plain;q=0.9"), Some(Encoding::Text));
+        assert_eq!(accept("text/csv, application/msgpack"), Some(Encoding::Csv));
+        assert_eq!(accept("text/csv;q=0, */*;q=0.1"), Some(Encoding::Json));
+        assert_eq!(accept("text/html, image/png"), None);
+    }
+
+    #[tokio::test]
+    async fn format_overrides_accept() {
+        let csv = negotiate("/stats?format=csv", Some("application/json")).await;
+        assert_eq!(csv, Ok(Encoding::Csv));
+        let text = negotiate("/stats?format=TEXT", Some("application/cbor")).await;
+        assert_eq!(text, Ok(Encoding::Text));
+        let cbor = negotiate("/stats", Some("application/cbor")).await;
+        assert_eq!(cbor, Ok(Encoding::Cbor));
+        assert_eq!(negotiate("/stats", None).await, Ok(Encoding::Json));
+
+        assert_eq!(negotiate("/stats?format=xml", None).await, Err(StatusCode::BAD_REQUEST));
+        let html = negotiate("/stats", Some("text/html")).await;
+        assert_eq!(html, Err(StatusCode::NOT_ACCEPTABLE));
+        // An unsupported Accept doesn't matter once format is given.
+        let json = negotiate("/stats?format=json", Some("text/html")).await;
+        assert_eq!(json, Ok(Encoding::Json));
+    }
+}
@@ -0,0 +1,494 @@
+//! Push exporters, and the flattening of the stats tree they share.
+//!
+//! Each exporter is an optional task that subscribes to the monitor's watch
+//! channel and forwards snapshots to an external system.  Labeled systems
+//! see the snapshot as [`Row`]s: the root and every array item of the
+//! serialized tree, each with its identifying labels and numeric fields.
+//! Dotted-name systems (StatsD, Graphite) see [`leaves`] named by their path.
+
+pub mod graphite;
+pub mod influx;
+pub mod mqtt;
+pub mod otlp;
+pub mod statsd;
+
+use std::fmt::Display;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::router::{is_wildcard, split_path};
+use crate::types::{BatteryStatus, DisplayState, DozeMode, StaticDeviceInfo, ThermalStatus};
+
+/// Largest UDP payload sent in one datagram; batches are split at line ends.
+pub const UDP_PAYLOAD: usize = 1400;
+
+/// String fields of array items that identify the item for as long as it
+/// exists, and so can label its series.  Any other string (a sensor's
+/// throttling `status`) changes over time and is a [`Row::states`] entry.
+const IDENTITY: [&str; 5] = ["name", "type", "mount_point", "model_name", "cores"];
+
+pub type Labels = Vec<(String, String)>;
+
+/// The root of the stats tree or one array item, flattened.
+pub struct Row {
+    /// Empty for the root, otherwise the singular names of the arrays it is
+    /// nested in: `[core]`, `[core, idle_state]`, `[volume]`, …
+    pub kind: Vec<String>,
+    /// Schema path of the row, for [`crate::meta`] lookups: empty for the
+    /// root, otherwise the arrays it is nested in with `*` for the item:
+    /// `[cores, *]`, `[cores, *, idle_states, *]`.
+    pub path: Vec<String>,
+    /// Item identity (`core="cpu0"`) and other identity strings
+    /// (`model="Cortex-A510"`), outermost item first.
+    pub labels: Labels,
+    /// Numeric and boolean fields, booleans as `0` / `1`.
+    pub fields: Vec<(String, f64)>,
+    /// String fields whose value changes over time, e.g. `("status", "Light")`.
+    pub states: Vec<(String, String)>,
+}
+
+impl Row {
+    /// Name of the row itself, joined with `sep`: `asmo`, `asmo_core`.
+    pub fn name(&self, prefix: &str, sep: &str) -> String {
+        let mut parts: Vec<&str> = vec![prefix];
+        parts.extend(self.kind.iter().map(String::as_str));
+        parts.retain(|p| !p.is_empty());
+        parts.join(sep)
+    }
+
+    /// Name of one field as a metric: `asmo_core_usage`.  A bare `value`
+    /// field is the item's own reading and takes the row's name.
+    pub fn metric(&self, prefix: &str, field: &str, sep: &str) -> String {
+        let name = self.name(prefix, sep);
+        match field {
+            "value" if !self.kind.is_empty() => name,
+            _ if name.is_empty() => field.to_owned(),
+            _ => format!("{name}{sep}{field}"),
+        }
+    }
+}
+
+/// Flatten a stats value tree (see [`crate::router::stats_to_value`]) into
+/// rows, root first.
+///
+/// The singular array name labels each item's `name` (`cores` → `core`), and
+/// the item's other [identity](IDENTITY) strings become labels with any
+/// `_name` suffix dropped.  Items without a `name` (cluster `time_in_state`)
+/// are labeled by their first field.  Strings of the root are not labels of anything — they are
+/// identity, which each exporter reports its own way.
+pub fn rows(tree: &Value) -> Vec<Row> {
+    let Value::Object(map) = tree else { return Vec::new() };
+
+    let mut rows = vec![Row {
+        kind: Vec::new(),
+        path: Vec::new(),
+        labels: Labels::new(),
+        fields: Vec::new(),
+        states: Vec::new(),
+    }];
+    for (key, value) in map {
+        match value {
+            Value::Array(items) => walk_items(&mut rows, &[], key, items, &Labels::new()),
+            other => {
+                if let Some(number) = number(other) {
+                    rows[0].fields.push((key.clone(), number));
+                }
+            }
+        }
+    }
+    rows
+}
+
+fn walk_items(rows: &mut Vec<Row>, path: &[String], key: &str, items: &[Value], parent: &Labels) {
+    let singular = item_label(key);
+    let mut path = path.to_vec();
+    path.extend([key.to_owned(), "*".to_owned()]);
+    let kind: Vec<String> = path.iter().step_by(2).map(|key| item_label(key).to_owned()).collect();
+
+    for item in items {
+        let Value::Object(fields) = item else { continue };
+
+        // The item's identity: its name, or else its first field.
+        let id = match fields.get("name") {
+            Some(_) => Some((singular.to_owned(), "name")),
+            None => fields.keys().next().map(|first| (first.clone(), first.as_str())),
+        };
+        let Some((id_label, id_field)) = id else { continue };
+
+        let mut labels = parent.clone();
+        labels.extend(label_value(&fields[id_field]).map(|value| (id_label, value)));
+        let mut states = Vec::new();
+        for (field, value) in fields.iter().filter(|(f, _)| f.as_str() != id_field) {
+            if IDENTITY.contains(&field.as_str()) {
+                if matches!(value, Value::String(_)) || is_string_list(value) {
+                    let label = field.strip_suffix("_name").unwrap_or(field);
+                    labels.extend(label_value(value).map(|value| (label.to_owned(), value)));
+                }
+            } else if let Value::String(state) = value {
+                states.push((field.clone(), state.clone()));
+            }
+        }
+
+        let index = rows.len();
+        rows.push(Row {
+            kind: kind.clone(),
+            path: path.clone(),
+            labels,
+            fields: Vec::new(),
+            states,
+        });
+
+        for (field, value) in fields.iter().filter(|(f, _)| f.as_str() != id_field) {
+            match value {
+                Value::Array(nested) if !is_string_list(value) => {
+                    let labels = rows[index].labels.clone();
+                    walk_items(rows, &path, field, nested, &labels);
+                }
+                other => {
+                    if let Some(number) = number(other) {
+                        rows[index].fields.push((field.clone(), number));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Label key for the items of an array: its singular name.  `storage` is
+/// `volume` so per-mount series don't share names with the
+/// `storage_free_gb` / `storage_total_gb` totals.
+fn item_label(array: &str) -> &str {
+    match array {
+        "storage" => "volume",
+        other => other.strip_suffix('s').unwrap_or(other),
+    }
+}
+
+/// Every state of an enum-valued field, as serialized, for exporters that
+/// report one series per state.  Array item fields are named by their row:
+/// `hal_temperature_status`.
+pub fn states(field: &str) -> Option<Vec<String>> {
+    fn names<T: Serialize>(all: &[T]) -> Vec<String> {
+        all.iter()
+            .filter_map(|state| serde_json::to_value(state).ok()?.as_str().map(str::to_owned))
+            .collect()
+    }
+
+    match field {
+        "battery_status" => Some(names(&BatteryStatus::ALL)),
+        "thermal_status" | "hal_temperature_status" => Some(names(&ThermalStatus::ALL)),
+        "display_state" => Some(names(&DisplayState::ALL)),
+        "device_idle" => Some(names(&DozeMode::ALL)),
+        _ => None,
+    }
+}
+
+fn is_string_list(value: &Value) -> bool {
+    matches!(value, Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_string))
+}
+
+/// Render a scalar (or list of strings, comma-joined) as a label value.
+pub fn label_value(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Array(items) => {
+            let parts: Vec<&str> = items.iter().filter_map(Value::as_str).collect();
+            Some(parts.join(","))
+        }
+        _ => None,
+    }
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::Bool(b) => Some(*b as u8 as f64),
+        _ => None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Leaves — for exporters that name every value by its path
+// ---------------------------------------------------------------------------
+
+/// Every numeric and boolean leaf of a stats tree with its path, array
+/// items addressed by name as in the HTTP API: `cores/cpu0/usage`.  Items
+/// without a `name` are addressed by their first field's value.
+pub fn leaves(tree: &Value) -> Vec<(Vec<String>, f64)> {
+    let mut out = Vec::new();
+    walk_leaves(tree, &mut Vec::new(), &mut out);
+    out
+}
+
+fn walk_leaves(value: &Value, path: &mut Vec<String>, out: &mut Vec<(Vec<String>, f64)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                path.push(key.clone());
+                walk_leaves(child, path, out);
+                path.pop();
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                let Value::Object(fields) = item else { continue };
+                let id_field = if fields.contains_key("name") {
+                    "name"
+                } else {
+                    match fields.keys().next() {
+                        Some(first) => first.as_str(),
+                        None => continue,
+                    }
+                };
+                let Some(id) = label_value(&fields[id_field]) else { continue };
+
+                path.push(id);
+                for (key, child) in fields.iter().filter(|(k, _)| k.as_str() != id_field) {
+                    path.push(key.clone());
+                    walk_leaves(child, path, out);
+                    path.pop();
+                }
+                path.pop();
+            }
+        }
+        other => {
+            if let Some(number) = number(other) {
+                out.push((path.clone(), number));
+            }
+        }
+    }
+}
+
+/// Router-style paths choosing which leaves to export: `battery_level`,
+/// `cores/*/usage`, `cores/all/usage,cur_freq`.  A path also selects
+/// everything below it, and no paths at all select everything.
+pub struct Selector(Vec<Vec<String>>);
+
+impl Selector {
+    pub fn new(paths: &[String]) -> Self {
+        Self(
+            paths
+                .iter()
+                .map(|p| split_path(p).into_iter().map(str::to_owned).collect())
+                .collect(),
+        )
+    }
+
+    pub fn matches(&self, path: &[String]) -> bool {
+        self.0.is_empty()
+            || self.0.iter().any(|selector| {
+                selector.len() <= path.len()
+                    && selector.iter().zip(path).all(|(want, have)| {
+                        is_wildcard(want) || want.split(',').any(|w| w.trim() == have)
+                    })
+            })
+    }
+}
+
+/// Dotted metric name of a leaf under `prefix`: `asmo.a065.cores.cpu0.usage`.
+/// Empty prefix segments (an unknown model) are left out.
+pub fn dotted_name(prefix: &str, path: &[String]) -> String {
+    let prefix = prefix.split('.').filter(|s| !s.is_empty()).map(str::to_owned);
+    let path = path.iter().map(|segment| name_segment(segment));
+    prefix.chain(path).collect::<Vec<_>>().join(".")
+}
+
+/// Expand `{manufacturer}`, `{product_model}`, `{soc_model}` and `{codename}`
+/// in a metric prefix, each as a lowercase name segment.
+pub fn expand_prefix(template: &str, device: &StaticDeviceInfo) -> String {
+    [
+        ("{manufacturer}", &*device.manufacturer),
+        ("{product_model}", &*device.product_model),
+        ("{soc_model}", &*device.soc_model),
+        ("{codename}", &*device.device.codename),
+    ]
+    .into_iter()
+    .fold(template.to_owned(), |prefix, (placeholder, value)| {
+        prefix.replace(placeholder, &name_segment(value))
+    })
+}
+
+/// Lowercase, with anything but letters, digits, `-` and `_` replaced, so a
+/// value can't split or break a dotted name.
+fn name_segment(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            c if c.is_ascii_alphanumeric() => c.to_ascii_lowercase(),
+            '-' | '_' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// Format a value for plaintext protocols: integers without a fraction,
+/// floats at the `f32` precision every float in the stats tree has.
+pub fn plain_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        (value as f32).to_string()
+    }
+}
+
+/// Split a batch into chunks of at most `max` bytes, breaking only between
+/// lines.  A single longer line is sent on its own, and a last line without
+/// a newline is kept.
+pub fn split_lines(body: &str, max: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut end = 0;
+
+    let line_ends = body.match_indices('\n').map(|(i, _)| i + 1);
+    let unterminated = (!body.is_empty() && !body.ends_with('\n')).then_some(body.len());
+    for line_end in line_ends.chain(unterminated) {
+        if line_end - start > max && end > start {
+            chunks.push(&body[start..end]);
+            start = end;
+        }
+        end = line_end;
+    }
+    if end > start {
+        chunks.push(&body[start..end]);
+    }
+    chunks
+}
+
+// ---------------------------------------------------------------------------
+// Outage logging
+// ---------------------------------------------------------------------------
+
+/// Logs the first failure of an outage and the recovery after it, rather
+/// than every failed attempt in between.
+pub struct Outage {
+    name: &'static str,
+    down: bool,
+}
+
+impl Outage {
+    pub fn new(name: &'static str) -> Self {
+        Self { name, down: false }
+    }
+
+    pub fn failed(&mut self, error: impl Display) {
+        if !self.down {
+            eprintln!("{}: target unreachable, retrying: {error}", self.name);
+            self.down = true;
+        }
+    }
+
+    pub fn recovered(&mut self) {
+        if self.down {
+            eprintln!("{}: target reachable again", self.name);
+            self.down = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owned(pairs: &[(&str, &str)]) -> Labels {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn rows_flatten_the_tree_with_identity_labels() {
+        let tree = serde_json::json!({
+            "battery_level": 81,
+            "screen_on": false,
+            "kernel_version": "6.1.0",
+            "cores": [{
+                "name": "cpu0",
+                "model_name": "Cortex-A510",
+                "governor": "schedutil",
+                "usage": 12.5,
+                "idle_states": [{ "name": "WFI", "usage": 3 }],
+            }],
+            "storage": [{ "name": "data", "mount_point": "/data", "free_gb": 84.5 }],
+            "clusters": [{ "cores": ["cpu0", "cpu1"], "time_in_state": [{ "freq": 300 }] }],
+        });
+        let rows = rows(&tree);
+
+        let summary: Vec<(String, Vec<String>, Labels)> = rows
+            .iter()
+            .map(|row| (row.name("asmo", "_"), row.path.clone(), row.labels.clone()))
+            .collect();
+        let path = |parts: &[&str]| parts.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("asmo".into(), path(&[]), owned(&[])),
+                (
+                    "asmo_core".into(),
+                    path(&["cores", "*"]),
+                    owned(&[("core", "cpu0"), ("model", "Cortex-A510")]),
+                ),
+                (
+                    "asmo_core_idle_state".into(),
+                    path(&["cores", "*", "idle_states", "*"]),
+                    owned(&[("core", "cpu0"), ("model", "Cortex-A510"), ("idle_state", "WFI")]),
+                ),
+                (
+                    "asmo_volume".into(),
+                    path(&["storage", "*"]),
+                    owned(&[("volume", "data"), ("mount_point", "/data")]),
+                ),
+                ("asmo_cluster".into(), path(&["clusters", "*"]), owned(&[("cores", "cpu0,cpu1")])),
+                (
+                    "asmo_cluster_time_in_state".into(),
+                    path(&["clusters", "*", "time_in_state", "*"]),
+                    owned(&[("cores", "cpu0,cpu1"), ("freq", "300")]),
+                ),
+            ]
+        );
+
+        // Root strings are identity, left to each exporter; numbers and
+        // booleans are fields.
+        assert_eq!(rows[0].fields, [("battery_level".into(), 81.0), ("screen_on".into(), 0.0)]);
+        assert!(rows[0].states.is_empty());
+        // A changing string is a state, not a label.
+        assert_eq!(rows[1].fields, [("usage".into(), 12.5)]);
+        assert_eq!(rows[1].states, [("governor".into(), "schedutil".into())]);
+        assert_eq!(rows[2].fields, [("usage".into(), 3.0)]);
+        // The first field labels an item without a name and isn't a field.
+        assert!(rows[5].fields.is_empty());
+    }
+
+    #[test]
+    fn metric_names_follow_the_row() {
+        let tree = serde_json::json!({
+            "hal_temperatures": [{ "name": "skin", "value": 33.5 }],
+        });
+        let rows = rows(&tree);
+        assert_eq!(rows[0].metric("asmo", "cpu_temp", "_"), "asmo_cpu_temp");
+        assert_eq!(rows[1].metric("asmo", "value", "."), "asmo.hal_temperature");
+        assert_eq!(rows[1].metric("", "status", "_"), "hal_temperature_status");
+    }
+
+    #[test]
+    fn split_lines_packs_whole_lines_up_to_max() {
+        let body = "aaaa\nbbbb\ncccc\n";
+        assert_eq!(split_lines(body, 10), ["aaaa\nbbbb\n", "cccc\n"]);
+        assert_eq!(split_lines(body, 15), [body]);
+        assert_eq!(split_lines(body, 14), ["aaaa\nbbbb\n", "cccc\n"]);
+        assert!(split_lines("", 10).is_empty());
+    }
+
+    #[test]
+    fn split_lines_sends_an_overlong_line_on_its_own() {
+        let body = "a\nbbbbbbbbbbbb\nc\n";
+        assert_eq!(split_lines(body, 4), ["a\n", "bbbbbbbbbbbb\n", "c\n"]);
+        assert_eq!(split_lines("bbbbbbbbbbbb\n", 4), ["bbbbbbbbbbbb\n"]);
+    }
+
+    #[test]
+    fn split_lines_keeps_a_last_line_without_newline() {
+        assert_eq!(split_lines("aaaa\nbbbb", 10), ["aaaa\nbbbb"]);
+        assert_eq!(split_lines("aaaa\nbbbb", 5), ["aaaa\n", "bbbb"]);
+        assert_eq!(split_lines("aaaa", 2), ["aaaa"]);
+    }
+}
@@ -0,0 +1,101 @@
+//! Graphite exporter: every numeric field over the Carbon plaintext protocol.
+//!
+//! One `path value timestamp` line per field, named by its router path under
+//! the configured prefix:
+//!
+//! ```text
+//! asmo.a065.battery_level 81 1760780412
+//! asmo.a065.cores.cpu0.usage 28.5 1760780412
+//! ```
+//!
+//! The TCP connection is opened lazily and dropped on the first write error;
+//! the next tick reconnects.  Snapshots taken while Carbon is unreachable are
+//! not sent.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::sync::watch;
+use tokio::time::MissedTickBehavior;
+
+use super::{Outage, Selector, dotted_name, expand_prefix, leaves, plain_number};
+use crate::clock::unix_millis;
+use crate::config::GraphiteConfig;
+use crate::router::stats_to_value;
+use crate::types::{StaticDeviceInfo, SystemStats};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// ---------------------------------------------------------------------------
+// Exporter task — spawned once when `[graphite]` is enabled.
+// ---------------------------------------------------------------------------
+
+pub async fn run(
+    rx: watch::Receiver<SystemStats>,
+    config: GraphiteConfig,
+    device: Arc<StaticDeviceInfo>,
+) {
+    let prefix = expand_prefix(&config.prefix, &device);
+    let selector = Selector::new(&config.paths);
+    let mut outage = Outage::new("graphite");
+    let mut stream: Option<TcpStream> = None;
+
+    let mut ticker = tokio::time::interval(config.interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        ticker.tick().await;
+        if rx.has_changed().is_err() {
+            break;
+        }
+        let tree = {
+            let stats = rx.borrow();
+            // Sequence 0 is the empty placeholder published before the first poll.
+            if stats.seq == 0 {
+                continue;
+            }
+            stats_to_value(&stats)
+        };
+
+        let timestamp = unix_millis() / 1000;
+        let mut body = String::new();
+        for (path, value) in leaves(&tree) {
+            if value.is_finite() && selector.matches(&path) {
+                let name = dotted_name(&prefix, &path);
+                body.push_str(&format!("{name} {} {timestamp}\n", plain_number(value)));
+            }
+        }
+
+        let connection = match stream.take() {
+            Some(connection) => Ok(connection),
+            None => connect(&config.address).await,
+        };
+        let sent = match connection {
+            Ok(mut connection) => match connection.write_all(body.as_bytes()).await {
+                Ok(()) => {
+                    stream = Some(connection);
+                    Ok(())
+                }
+                Err(e) => Err(e.to_string()),
+            },
+            Err(e) => Err(e),
+        };
+        match sent {
+            Ok(()) => outage.recovered(),
+            Err(e) => outage.failed(e),
+        }
+    }
+}
+
+async fn connect(address: &str) -> Result<TcpStream, String> {
+    match tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(address)).await {
+        Ok(Ok(stream)) => {
+            let _ = stream.set_nodelay(true);
+            Ok(stream)
+        }
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err("connect timed out".to_owned()),
+    }
+}
@@ -0,0 +1,430 @@
+//! InfluxDB line-protocol exporter.
+//!
+//! Every `interval` the latest snapshot becomes one line per [`Row`]:
+//!
+//! ```text
+//! asmo,manufacturer=Nothing,product_model=A065,… battery_level=81,cpu_temp=34.4 1760780412345
+//! asmo_core,manufacturer=Nothing,…,core=cpu0,model=Cortex-A510 usage=28.5 1760780412345
+//! asmo_hal_temperature,…,hal_temperature=skin,type=SKIN value=32.1,status="None" 1760780412345
+//! ```
+//!
+//! Only identity strings are tags; an item's changing strings (a sensor's
+//! `status`) are string fields, so they don't split its series.
+//!
+//! Lines are batched and written to the v2 HTTP API or a UDP listener.  A
+//! batch that can't be delivered is spooled to disk and replayed, oldest
+//! first, once the target is reachable again.  Delivery runs in its own
+//! task, so sampling keeps its pace while requests time out.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write as _};
+use std::net::UdpSocket;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::sync::{mpsc, watch};
+use tokio::time::MissedTickBehavior;
+
+use super::{UDP_PAYLOAD, rows, split_lines};
+use crate::clock::unix_millis;
+use crate::config::InfluxConfig;
+use crate::router::stats_to_value;
+use crate::types::{StaticDeviceInfo, SystemStats};
+
+const MEASUREMENT_PREFIX: &str = "asmo";
+
+// ---------------------------------------------------------------------------
+// Exporter task — spawned once when `[influx]` is enabled.
+// ---------------------------------------------------------------------------
+
+pub async fn run(
+    rx: watch::Receiver<SystemStats>,
+    config: InfluxConfig,
+    device: Arc<StaticDeviceInfo>,
+) {
+    let client = match Client::new(&config) {
+        Ok(client) => Arc::new(client),
+        Err(e) => {
+            eprintln!("influx: {e}");
+            return;
+        }
+    };
+
+    let mut tags = String::new();
+    for (key, value) in [
+        ("manufacturer", &device.manufacturer),
+        ("product_model", &device.product_model),
+        ("soc_model", &device.soc_model),
+    ] {
+        push_tag(&mut tags, key, value);
+    }
+
+    let mut sample = tokio::time::interval(config.interval);
+    sample.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut flush = tokio::time::interval(config.flush_interval);
+    flush.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let (batches, queue) = mpsc::unbounded_channel();
+    tokio::spawn(deliver(client, queue));
+
+    let mut batch = String::new();
+    let mut lines = 0_usize;
+
+    loop {
+        tokio::select! {
+            _ = sample.tick() => {
+                if rx.has_changed().is_err() {
+                    break;
+                }
+                let tree = {
+                    let stats = rx.borrow();
+                    // Sequence 0 is the empty placeholder published before the first poll.
+                    if stats.seq == 0 {
+                        continue;
+                    }
+                    stats_to_value(&stats)
+                };
+                lines += write_lines(&mut batch, &tags, &tree, unix_millis());
+                if lines < config.batch_size {
+                    continue;
+                }
+            }
+            _ = flush.tick() => {}
+        }
+
+        // An empty batch still gives the spool a chance to drain.
+        lines = 0;
+        if batches.send(std::mem::take(&mut batch)).is_err() {
+            break;
+        }
+    }
+}
+
+/// Flush batches as they come.  Batches queued while a flush was stuck on
+/// an unreachable target are sent together, so the queue stays short.
+async fn deliver(client: Arc<Client>, mut queue: mpsc::UnboundedReceiver<String>) {
+    while let Some(mut body) = queue.recv().await {
+        while let Ok(more) = queue.try_recv() {
+            body.push_str(&more);
+        }
+        let client = Arc::clone(&client);
+        if tokio::task::spawn_blocking(move || client.flush(body)).await.is_err() {
+            break;
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Line protocol
+// ---------------------------------------------------------------------------
+
+/// Append one line per row of `tree`, returning how many were written.
+/// Rows with only string states are written too: a field set may be all
+/// strings.
+fn write_lines(out: &mut String, tags: &str, tree: &Value, timestamp: u64) -> usize {
+    let mut written = 0;
+
+    for row in rows(tree) {
+        let fields: Vec<&(String, f64)> =
+            row.fields.iter().filter(|(_, v)| v.is_finite()).collect();
+        if fields.is_empty() && row.states.is_empty() {
+            continue;
+        }
+
+        out.push_str(&escape(&row.name(MEASUREMENT_PREFIX, "_"), ", "));
+        out.push_str(tags);
+        for (key, value) in &row.labels {
+            push_tag(out, key, value);
+        }
+        let mut separator = ' ';
+        for (key, value) in fields {
+            out.push(separator);
+            separator = ',';
+            out.push_str(&escape(key, ",= "));
+            out.push('=');
+            out.push_str(&value.to_string());
+        }
+        for (key, value) in &row.states {
+            out.push(separator);
+            separator = ',';
+            out.push_str(&escape(key, ",= "));
+            out.push_str("=\"");
+            out.push_str(&value.replace('\\', "\\\\").replace('"', "\\\""));
+            out.push('"');
+        }
+        out.push(' ');
+        out.push_str(&timestamp.to_string());
+        out.push('\n');
+        written += 1;
+    }
+
+    written
+}
+
+/// Append `,key=value`; empty values are not allowed as tags and are skipped.
+fn push_tag(out: &mut String, key: &str, value: &str) {
+    if value.is_empty() {
+        return;
+    }
+    out.push(',');
+    out.push_str(&escape(key, ",= "));
+    out.push('=');
+    out.push_str(&escape(value, ",= "));
+}
+
+/// Backslash-escape every character of `special`.  Newlines can't be
+/// escaped in line protocol, so they become escaped spaces.
+fn escape(s: &str, special: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\n' | '\r' => out.push_str("\\ "),
+            c if special.contains(c) => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Delivery
+// ---------------------------------------------------------------------------
+
+enum Target {
+    Http { agent: ureq::Agent, url: String, token: Option<String>, org: String, bucket: String },
+    Udp { socket: UdpSocket, addr: String },
+}
+
+enum SendError {
+    /// The target is unreachable or overloaded — keep the batch for later.
+    Retry(String),
+    /// The target rejected the batch — retrying won't help.
+    Drop(String),
+}
+
+struct Client {
+    target: Target,
+    spool: Spool,
+    /// Whether the last delivery attempt succeeded, so outages are logged
+    /// once rather than on every flush.
+    healthy: AtomicBool,
+}
+
+impl Client {
+    fn new(config: &InfluxConfig) -> Result<Self, String> {
+        let target = if let Some(addr) = config.url.strip_prefix("udp://") {
+            let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| format!("udp socket: {e}"))?;
+            Target::Udp { socket, addr: addr.trim_end_matches('/').to_owned() }
+        } else if config.url.starts_with("http://") || config.url.starts_with("https://") {
+            let agent = ureq::Agent::config_builder()
+                .timeout_global(Some(Duration::from_secs(10)))
+                .build()
+                .into();
+            Target::Http {
+                agent,
+                url: format!("{}/api/v2/write", config.url.trim_end_matches('/')),
+                token: config.token.clone(),
+                org: config.org.clone(),
+                bucket: config.bucket.clone(),
+            }
+        } else {
+            return Err(format!(
+                "unsupported url `{}` (expected http://, https:// or udp://)",
+                config.url
+            ));
+        };
+
+        let spool = Spool::open(config.spool_dir(), config.spool_max_mb * 1024 * 1024)
+            .map_err(|e| format!("spool: {e}"))?;
+
+        Ok(Self { target, spool, healthy: AtomicBool::new(true) })
+    }
+
+    /// Deliver anything spooled, oldest first, then `batch`.  Whatever can't
+    /// be delivered is spooled for the next attempt.
+    fn flush(&self, batch: String) {
+        let mut reachable = true;
+        for path in self.spool.pending() {
+            let Ok(body) = fs::read_to_string(&path) else { continue };
+            match self.send(&body) {
+                Ok(()) => {}
+                Err(SendError::Drop(e)) => eprintln!("influx: dropping spooled batch: {e}"),
+                Err(SendError::Retry(e)) => {
+                    self.mark(Err(&e));
+                    reachable = false;
+                    break;
+                }
+            }
+            let _ = fs::remove_file(path);
+        }
+
+        if batch.is_empty() {
+            if reachable {
+                self.mark(Ok(()));
+            }
+            return;
+        }
+
+        if reachable {
+            match self.send(&batch) {
+                Ok(()) => return self.mark(Ok(())),
+                Err(SendError::Drop(e)) => return eprintln!("influx: dropping batch: {e}"),
+                Err(SendError::Retry(e)) => self.mark(Err(&e)),
+            }
+        }
+        if let Err(e) = self.spool.push(&batch) {
+            eprintln!("influx: spool write failed, batch lost: {e}");
+        }
+    }
+
+    fn send(&self, body: &str) -> Result<(), SendError> {
+        match &self.target {
+            Target::Http { agent, url, token, org, bucket } => {
+                let mut request = agent
+                    .post(url)
+                    .query("org", org)
+                    .query("bucket", bucket)
+                    .query("precision", "ms")
+                    .header("Content-Type", "text/plain; charset=utf-8");
+                if let Some(token) = token {
+                    request = request.header("Authorization", format!("Token {token}"));
+                }
+
+                match request.send(body) {
+                    Ok(_) => Ok(()),
+                    Err(ureq::Error::StatusCode(code)) if code == 429 || code >= 500 => {
+                        Err(SendError::Retry(format!("HTTP {code}")))
+                    }
+                    Err(ureq::Error::StatusCode(code)) => {
+                        Err(SendError::Drop(format!("HTTP {code}")))
+                    }
+                    Err(e) => Err(SendError::Retry(e.to_string())),
+                }
+            }
+            Target::Udp { socket, addr } => {
+                for datagram in split_lines(body, UDP_PAYLOAD) {
+                    socket
+                        .send_to(datagram.as_bytes(), addr.as_str())
+                        .map_err(|e| SendError::Retry(e.to_string()))?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Log transitions between reachable and unreachable.
+    fn mark(&self, result: Result<(), &str>) {
+        let healthy = result.is_ok();
+        if self.healthy.swap(healthy, Ordering::Relaxed) == healthy {
+            return;
+        }
+        match result {
+            Ok(()) => eprintln!("influx: target reachable again"),
+            Err(e) => eprintln!("influx: target unreachable, spooling batches: {e}"),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// On-disk spool — one `<unix ms>.lp` file per undelivered batch.
+// ---------------------------------------------------------------------------
+
+struct Spool {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+impl Spool {
+    fn open(dir: PathBuf, max_bytes: u64) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir, max_bytes })
+    }
+
+    /// Spooled batches, oldest first.
+    fn pending(&self) -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(&self.dir) else { return Vec::new() };
+
+        let mut files: Vec<(u64, PathBuf)> = entries
+            .filter_map(Result::ok)
+            .filter_map(|e| {
+                let start = e.file_name().to_string_lossy().strip_suffix(".lp")?.parse().ok()?;
+                Some((start, e.path()))
+            })
+            .collect();
+        files.sort_unstable_by_key(|&(start, _)| start);
+        files.into_iter().map(|(_, path)| path).collect()
+    }
+
+    /// Add a batch, then drop the oldest ones while over the size limit.
+    fn push(&self, batch: &str) -> io::Result<()> {
+        let path = self.dir.join(format!("{}.lp", unix_millis()));
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(batch.as_bytes())?;
+        file.sync_data()?;
+
+        let pending = self.pending();
+        let sizes: Vec<u64> =
+            pending.iter().map(|p| fs::metadata(p).map_or(0, |m| m.len())).collect();
+        let mut total: u64 = sizes.iter().sum();
+        for (path, size) in pending.iter().zip(sizes) {
+            if total <= self.max_bytes {
+                break;
+            }
+            let _ = fs::remove_file(path);
+            total -= size;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn escape_backslashes_special_characters_and_flattens_newlines() {
+        assert_eq!(escape("a b,c=d", ",= "), "a\\ b\\,c\\=d");
+        assert_eq!(escape("a b,c=d", ", "), "a\\ b\\,c=d");
+        assert_eq!(escape("two\nlines\r", ",= "), "two\\ lines\\ ");
+        assert_eq!(escape("plain", ",= "), "plain");
+    }
+
+    #[test]
+    fn write_lines_renders_one_line_per_row() {
+        let tree = json!({
+            "cpu_usage": 12.5,
+            "charging": true,
+            "cores": [{ "name": "cpu0", "usage": 40.0, "governor": "schedutil" }],
+            "storage": [{ "name": "data", "mount_point": "/data", "free_gb": f64::NAN }],
+        });
+
+        let mut out = String::new();
+        let written = write_lines(&mut out, ",host=phone", &tree, 1000);
+
+        assert_eq!(written, 2);
+        assert_eq!(
+            out,
+            "asmo,host=phone cpu_usage=12.5,charging=1 1000\n\
+             asmo_core,host=phone,core=cpu0 usage=40,governor=\"schedutil\" 1000\n"
+        );
+    }
+
+    #[test]
+    fn write_lines_keeps_rows_with_only_string_states() {
+        let tree = json!({
+            "thermal": [{ "name": "skin", "status": "Light \"warm\"", "value": null }],
+        });
+
+        let mut out = String::new();
+        assert_eq!(write_lines(&mut out, "", &tree, 7), 1);
+        assert_eq!(out, "asmo_thermal,thermal=skin status=\"Light \\\"warm\\\"\" 7\n");
+    }
+}
@@ -0,0 +1,359 @@
+//! MQTT publisher with Home Assistant discovery.
+//!
+//! Every field is its own topic under `<topic_prefix>/<device_id>/`, using
+//! the same paths as the HTTP API:
+//!
+//! ```text
+//! asmo/taro/battery_level        81
+//! asmo/taro/battery_status       Charging
+//! asmo/taro/cores/cpu0/usage     28.5
+//! asmo/taro/status               online   (last will: offline)
+//! ```
+//!
+//! Values are published every `interval` when they change, retained by
+//! default.  With discovery on, each field is announced once per connection
+//! under `<discovery_prefix>/…/config` so it shows up in Home Assistant as a
+//! sensor of one device; fields that appear later (a mounted SD card, a
+//! thermal sensor the HAL reports late) are announced when first seen.
+//! Nested arrays (idle states, cluster frequency residency) and the `device`
+//! object are not published.
+//!
+//! Publishing never waits on the broker: while it is down the client's
+//! request queue fills up, and messages that don't fit are dropped, counted
+//! and sent again on a later tick.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rumqttc::{AsyncClient, Event, EventLoop, LastWill, MqttOptions, Packet, QoS};
+use serde_json::{Map, Value, json};
+use tokio::sync::watch;
+use tokio::time::MissedTickBehavior;
+
+use crate::config::MqttConfig;
+use crate::meta::{FieldMeta, Registry};
+use crate::router::stats_to_value;
+use crate::types::{StaticDeviceInfo, SystemStats};
+
+/// Wait between reconnection attempts once the broker connection drops.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Requests the client buffers for the event loop; beyond that, publishes
+/// are dropped rather than awaited.
+const REQUEST_QUEUE: usize = 256;
+
+// ---------------------------------------------------------------------------
+// Publisher task — spawned once when `[mqtt]` is enabled.
+// ---------------------------------------------------------------------------
+
+pub async fn run(
+    rx: watch::Receiver<SystemStats>,
+    config: MqttConfig,
+    device: Arc<StaticDeviceInfo>,
+    meta: Arc<Registry>,
+) {
+    let device_id = config.device_id.clone().unwrap_or_else(|| default_device_id(&device));
+    let base = format!("{}/{device_id}", config.topic_prefix.trim_end_matches('/'));
+    let status_topic = format!("{base}/status");
+
+    let mut options = MqttOptions::new(format!("asmo-{device_id}"), &config.host, config.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    options.set_last_will(LastWill::new(&status_topic, "offline", QoS::AtLeastOnce, true));
+    if let Some(username) = &config.username {
+        options.set_credentials(username, config.password.clone().unwrap_or_default());
+    }
+
+    let (client, eventloop) = AsyncClient::new(options, REQUEST_QUEUE);
+    let (connected_tx, mut connected) = watch::channel(0_u64);
+    tokio::spawn(drive(eventloop, connected_tx));
+
+    let mut ticker = tokio::time::interval(config.interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    // Last payload published per topic and the leaves announced for
+    // discovery, both cleared on every (re)connect so the broker gets a
+    // full picture again.
+    let mut published: HashMap<String, String> = HashMap::new();
+    let mut announced: HashSet<String> = HashSet::new();
+    let mut drops = Drops::default();
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            changed = connected.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                let online = client.try_publish(&status_topic, QoS::AtLeastOnce, true, "online");
+                drops.record(online.is_ok());
+                published.clear();
+                announced.clear();
+            }
+        }
+        if rx.has_changed().is_err() {
+            break;
+        }
+        if *connected.borrow() == 0 {
+            continue;
+        }
+
+        let tree = {
+            let stats = rx.borrow();
+            // Sequence 0 is the empty placeholder published before the first poll.
+            if stats.seq == 0 {
+                continue;
+            }
+            stats_to_value(&stats)
+        };
+        let leaves = leaves(&tree);
+
+        if config.discovery {
+            let device_block = device_block(&device, &device_id);
+            for leaf in &leaves {
+                if announced.contains(&leaf.path) {
+                    continue;
+                }
+                let segments: Vec<&str> = leaf.path.split('/').collect();
+                let field = meta.field(&segments);
+                let (topic, payload) =
+                    discovery(&config, &base, &device_id, &device_block, leaf, field.as_ref());
+                let sent = client.try_publish(topic, QoS::AtLeastOnce, true, payload).is_ok();
+                drops.record(sent);
+                if sent {
+                    announced.insert(leaf.path.clone());
+                }
+            }
+        }
+
+        for leaf in &leaves {
+            let payload = payload(leaf.value);
+            let topic = format!("{base}/{}", leaf.path);
+            if published.get(&topic) == Some(&payload) {
+                continue;
+            }
+            let sent = client.try_publish(&topic, QoS::AtMostOnce, config.retain, payload.clone());
+            drops.record(sent.is_ok());
+            if sent.is_ok() {
+                published.insert(topic, payload);
+            }
+        }
+    }
+}
+
+/// Publishes that didn't fit the request queue.  The first drop of a run
+/// is logged, and the run's count once messages go through again.
+#[derive(Default)]
+struct Drops {
+    /// Every message dropped since the publisher started.
+    total: u64,
+    /// Messages dropped in the current run; `0` while publishing works.
+    run: u64,
+}
+
+impl Drops {
+    fn record(&mut self, sent: bool) {
+        match (sent, self.run) {
+            (false, 0) => {
+                eprintln!("mqtt: request queue full, dropping messages until the broker is back");
+                self.run = 1;
+                self.total += 1;
+            }
+            (false, _) => {
+                self.run += 1;
+                self.total += 1;
+            }
+            (true, 0) => {}
+            (true, run) => {
+                eprintln!("mqtt: publishing again, {run} dropped ({} in total)", self.total);
+                self.run = 0;
+            }
+        }
+    }
+}
+
+/// Poll the connection, reconnecting after errors.  Every successful
+/// (re)connect bumps the generation in `connected`; `0` means never connected.
+async fn drive(mut eventloop: EventLoop, connected: watch::Sender<u64>) {
+    // Only the first failure of an outage is logged.
+    let mut up = true;
+    while !connected.is_closed() {
+        match eventloop.poll().await {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                up = true;
+                connected.send_modify(|generation| *generation += 1);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                if up {
+                    eprintln!("mqtt: broker unreachable, retrying: {e}");
+                }
+                up = false;
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        }
+    }
+}
+
+/// Lowercase codename (or model), reduced to characters safe in a topic.
+fn default_device_id(device: &StaticDeviceInfo) -> String {
+    let name = match &*device.device.codename {
+        "" => &*device.product_model,
+        codename => codename,
+    };
+    let id: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    if id.is_empty() { "android".to_owned() } else { id }
+}
+
+// ---------------------------------------------------------------------------
+// Leaves — one topic each
+// ---------------------------------------------------------------------------
+
+struct Leaf<'a> {
+    /// Topic suffix, the same as the HTTP path: `cores/cpu0/usage`.
+    path: String,
+    /// Array item name, if the leaf belongs to one.
+    item: Option<&'a str>,
+    field: &'a str,
+    value: &'a Value,
+}
+
+/// Scalar fields of the root and of named array items.
+fn leaves(tree: &Value) -> Vec<Leaf<'_>> {
+    let Value::Object(map) = tree else { return Vec::new() };
+    let mut out = Vec::new();
+
+    for (key, value) in map {
+        match value {
+            Value::Array(items) => {
+                for item in items {
+                    let Some(name) = item.get("name").and_then(Value::as_str) else { continue };
+                    let Value::Object(fields) = item else { continue };
+                    let scalars = fields.iter().filter(|(f, v)| *f != "name" && is_scalar(v));
+                    for (field, value) in scalars {
+                        out.push(Leaf {
+                            path: format!("{key}/{name}/{field}"),
+                            item: Some(name),
+                            field,
+                            value,
+                        });
+                    }
+                }
+            }
+            value if is_scalar(value) => {
+                out.push(Leaf { path: key.clone(), item: None, field: key, value });
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+fn is_scalar(value: &Value) -> bool {
+    matches!(value, Value::Number(_) | Value::String(_) | Value::Bool(_))
+}
+
+/// Plain-text payload.  Floats are printed at `f32` precision, which is what
+/// every float in [`SystemStats`] is.
+fn payload(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) if n.is_f64() => (n.as_f64().unwrap_or_default() as f32).to_string(),
+        other => other.to_string(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Home Assistant discovery
+// ---------------------------------------------------------------------------
+
+/// The `device` block shared by every entity, grouping them in HA.
+fn device_block(device: &StaticDeviceInfo, device_id: &str) -> Value {
+    json!({
+        "identifiers": [format!("asmo_{device_id}")],
+        "name": format!("{} {}", device.manufacturer, device.product_model).trim(),
+        "manufacturer": &*device.manufacturer,
+        "model": &*device.product_model,
+        "hw_version": &*device.soc_model,
+        "sw_version": format!("Android {}", device.android_version),
+    })
+}
+
+/// Discovery topic and config payload for one leaf.
+fn discovery(
+    config: &MqttConfig,
+    base: &str,
+    device_id: &str,
+    device_block: &Value,
+    leaf: &Leaf,
+    meta: Option<&FieldMeta>,
+) -> (String, String) {
+    let object_id = leaf.path.replace('/', "_");
+    let name = match leaf.item {
+        Some(item) => format!("{item} {}", leaf.field.replace('_', " ")),
+        None => humanize(leaf.field),
+    };
+
+    let mut entity = Map::new();
+    entity.insert("name".into(), name.into());
+    entity.insert("unique_id".into(), format!("asmo_{device_id}_{object_id}").into());
+    entity.insert("state_topic".into(), format!("{base}/{}", leaf.path).into());
+    entity.insert("availability_topic".into(), format!("{base}/status").into());
+    entity.insert("device".into(), device_block.clone());
+
+    let component = match leaf.value {
+        Value::Bool(_) => {
+            entity.insert("payload_on".into(), "true".into());
+            entity.insert("payload_off".into(), "false".into());
+            "binary_sensor"
+        }
+        Value::Number(_) => {
+            if let Some(unit) = meta.and_then(|m| m.unit.as_deref()) {
+                entity.insert("unit_of_measurement".into(), unit.into());
+            }
+            // Static values (boot time, frequency limits) are facts, not
+            // measurements, and their unit doesn't make them a duration.
+            match meta {
+                Some(meta) if meta.is_static() => {}
+                _ => {
+                    if let Some(device_class) = meta.and_then(|m| device_class(m, leaf.field)) {
+                        entity.insert("device_class".into(), device_class.into());
+                    }
+                    entity.insert("state_class".into(), "measurement".into());
+                }
+            }
+            "sensor"
+        }
+        _ => "sensor",
+    };
+
+    let topic = format!("{}/{component}/{device_id}/{object_id}/config", config.discovery_prefix);
+    (topic, Value::Object(entity).to_string())
+}
+
+/// Home Assistant device class of a numeric field, from its unit (see
+/// [`crate::meta`]).
+fn device_class(meta: &FieldMeta, field: &str) -> Option<&'static str> {
+    match meta.unit.as_deref()? {
+        "%" if field == "battery_level" => Some("battery"),
+        "°C" => Some("temperature"),
+        "Hz" | "MHz" => Some("frequency"),
+        "MB" | "GB" => Some("data_size"),
+        "MB/s" => Some("data_rate"),
+        "s" => Some("duration"),
+        _ => None,
+    }
+}
+
+/// `battery_level` → `Battery level`.
+fn humanize(field: &str) -> String {
+    let text = field.replace('_', " ");
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => text,
+    }
+}
@@ -0,0 +1,254 @@
+//! OpenTelemetry exporter: every numeric field as an OTLP gauge, sent as
+//! JSON to a collector's `/v1/metrics` endpoint.
+//!
+//! Metric names are the dotted [`Row`](super::Row) names (`asmo.cpu_temp`,
+//! `asmo.core.usage`), and array item labels become data-point attributes:
+//!
+//! ```text
+//! asmo.core.usage  {core="cpu0", model="Cortex-A510"}  28.5
+//! asmo.core.usage  {core="cpu4", model="Cortex-A710"}  61.0
+//! ```
+//!
+//! Units come from the field metadata (see [`crate::meta`]), in UCUM as OTLP
+//! expects.  Enum fields (`thermal_status`, a HAL sensor's `status`) become
+//! state gauges like the Prometheus state sets: one point per state, set to
+//! `1` for the current one and told apart by a `state` attribute.
+//!
+//! The device's identity is sent once per request as resource attributes.
+//! A snapshot that can't be delivered is dropped; the next one is a full
+//! picture anyway.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::{Value, json};
+use tokio::sync::watch;
+use tokio::time::MissedTickBehavior;
+
+use super::{Outage, Row, rows, states};
+use crate::clock::unix_millis;
+use crate::config::OtlpConfig;
+use crate::meta::Registry;
+use crate::router::stats_to_value;
+use crate::types::{StaticDeviceInfo, SystemStats};
+
+const METRIC_PREFIX: &str = "asmo";
+
+// ---------------------------------------------------------------------------
+// Exporter task — spawned once when `[otlp]` is enabled.
+// ---------------------------------------------------------------------------
+
+pub async fn run(
+    rx: watch::Receiver<SystemStats>,
+    config: OtlpConfig,
+    device: Arc<StaticDeviceInfo>,
+    meta: Arc<Registry>,
+) {
+    let endpoint = config.metrics_url();
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(Duration::from_secs(10)))
+        .build()
+        .into();
+    let resource = resource(&device, &config.service_name);
+    let headers = Arc::new(config.headers);
+    let mut outage = Outage::new("otlp");
+
+    let mut ticker = tokio::time::interval(config.interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        ticker.tick().await;
+        if rx.has_changed().is_err() {
+            break;
+        }
+        let tree = {
+            let stats = rx.borrow();
+            // Sequence 0 is the empty placeholder published before the first poll.
+            if stats.seq == 0 {
+                continue;
+            }
+            stats_to_value(&stats)
+        };
+
+        let body = json!({
+            "resourceMetrics": [{
+                "resource": resource,
+                "scopeMetrics": [{
+                    "scope": { "name": METRIC_PREFIX, "version": env!("CARGO_PKG_VERSION") },
+                    "metrics": gauges(&tree, &meta, unix_millis() * 1_000_000),
+                }],
+            }],
+        })
+        .to_string();
+
+        let (agent, endpoint, headers) = (agent.clone(), endpoint.clone(), Arc::clone(&headers));
+        let sent = tokio::task::spawn_blocking(move || send(&agent, &endpoint, &headers, &body));
+        match sent.await {
+            Ok(Ok(())) => outage.recovered(),
+            Ok(Err(e)) => outage.failed(e),
+            Err(e) => outage.failed(e),
+        }
+    }
+}
+
+fn send(
+    agent: &ureq::Agent,
+    endpoint: &str,
+    headers: &HashMap<String, String>,
+    body: &str,
+) -> Result<(), String> {
+    let mut request = agent.post(endpoint).header("Content-Type", "application/json");
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    match request.send(body) {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::StatusCode(code)) => Err(format!("HTTP {code}")),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// OTLP/JSON encoding
+// ---------------------------------------------------------------------------
+
+/// Resource attributes identifying the phone, following the OpenTelemetry
+/// semantic conventions where they have a name for it.
+fn resource(device: &StaticDeviceInfo, service_name: &str) -> Value {
+    let attributes: Vec<Value> = [
+        ("service.name", service_name),
+        ("service.version", env!("CARGO_PKG_VERSION")),
+        ("device.manufacturer", &*device.manufacturer),
+        ("device.model.name", &*device.product_model),
+        ("device.model.identifier", &*device.device.codename),
+        ("os.type", "linux"),
+        ("os.name", "Android"),
+        ("os.version", &*device.android_version),
+        ("asmo.soc_model", &*device.soc_model),
+        ("asmo.kernel_version", &*device.kernel_version),
+    ]
+    .into_iter()
+    .filter(|(_, value)| !value.is_empty())
+    .map(|(key, value)| attribute(key, value))
+    .collect();
+
+    json!({ "attributes": attributes })
+}
+
+/// One gauge per metric name, with a data point per row that has it, in
+/// first-seen order.
+fn gauges(tree: &Value, meta: &Registry, time_unix_nano: u64) -> Vec<Value> {
+    // 64-bit integers are strings in the JSON encoding of protobuf.
+    let time = time_unix_nano.to_string();
+    let mut gauges = Gauges::default();
+
+    for row in rows(tree) {
+        let attributes: Vec<Value> =
+            row.labels.iter().map(|(key, value)| attribute(key, value)).collect();
+        for (field, value) in row.fields.iter().filter(|(_, v)| v.is_finite()) {
+            let name = row.metric(METRIC_PREFIX, field, ".");
+            let point = json!({ "attributes": attributes, "timeUnixNano": time, "asDouble": value });
+            gauges.push(name, point, || unit(meta, &row, field));
+        }
+        for (field, current) in &row.states {
+            let name = row.metric(METRIC_PREFIX, field, ".");
+            let all = states(&row.metric("", field, "_"));
+            gauges.push_states(name, &attributes, all, current, &time);
+        }
+    }
+
+    // Root strings are identity, sent as resource attributes, except enums.
+    if let Value::Object(map) = tree {
+        for (key, value) in map {
+            let Value::String(current) = value else { continue };
+            if let Some(all) = states(key) {
+                let name = format!("{METRIC_PREFIX}.{key}");
+                gauges.push_states(name, &[], Some(all), current, &time);
+            }
+        }
+    }
+
+    gauges.into_metrics()
+}
+
+/// Gauges being assembled, keyed by metric name.
+#[derive(Default)]
+struct Gauges {
+    names: Vec<String>,
+    metrics: HashMap<String, (Option<&'static str>, Vec<Value>)>,
+}
+
+impl Gauges {
+    /// Add a data point; `unit` is only looked up for a metric's first one.
+    fn push(&mut self, name: String, point: Value, unit: impl FnOnce() -> Option<&'static str>) {
+        self.metrics
+            .entry(name)
+            .or_insert_with_key(|name| {
+                self.names.push(name.clone());
+                (unit(), Vec::new())
+            })
+            .1
+            .push(point);
+    }
+
+    /// One point per state of an enum field, `1` for `current`.  A field
+    /// without a known state list reports only its current state.
+    fn push_states(
+        &mut self,
+        name: String,
+        attributes: &[Value],
+        all: Option<Vec<String>>,
+        current: &str,
+        time: &str,
+    ) {
+        for state in all.unwrap_or_else(|| vec![current.to_owned()]) {
+            let mut attributes = attributes.to_vec();
+            attributes.push(attribute("state", &state));
+            let active = if state == current { "1" } else { "0" };
+            let point =
+                json!({ "attributes": attributes, "timeUnixNano": time, "asInt": active });
+            self.push(name.clone(), point, || None);
+        }
+    }
+
+    fn into_metrics(mut self) -> Vec<Value> {
+        self.names
+            .into_iter()
+            .map(|name| {
+                let (unit, data_points) = self.metrics.remove(&name).unwrap_or_default();
+                let mut metric = json!({ "name": name, "gauge": { "dataPoints": data_points } });
+                if let Some(unit) = unit {
+                    metric["unit"] = unit.into();
+                }
+                metric
+            })
+            .collect()
+    }
+}
+
+/// UCUM unit of a row's field, from its metadata.
+fn unit(meta: &Registry, row: &Row, field: &str) -> Option<&'static str> {
+    let mut segments: Vec<&str> = row.path.iter().map(String::as_str).collect();
+    segments.push(field);
+    let unit = meta.field(&segments)?.unit?;
+
+    match unit.as_str() {
+        "%" => Some("%"),
+        "°C" => Some("Cel"),
+        "Hz" => Some("Hz"),
+        "MHz" => Some("MHz"),
+        "s" => Some("s"),
+        "MB" => Some("MBy"),
+        "GB" => Some("GBy"),
+        "MB/s" => Some("MBy/s"),
+        "ops/s" => Some("{operation}/s"),
+        "px" => Some("{pixel}"),
+        "dpi" => Some("{dot}/[in_i]"),
+        _ => None,
+    }
+}
+
+fn attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
@@ -0,0 +1,145 @@
+//! StatsD exporter: every numeric field as a gauge, over UDP.
+//!
+//! Names are the router path under the configured prefix:
+//!
+//! ```text
+//! asmo.a065.battery_level:81|g
+//! asmo.a065.cores.cpu0.usage:28.5|g
+//! ```
+//!
+//! UDP has no connection to lose; the address is resolved again on every
+//! send, so a daemon that moves or restarts is picked up by the next tick.
+
+use std::sync::Arc;
+
+use tokio::net::UdpSocket;
+use tokio::sync::watch;
+use tokio::time::MissedTickBehavior;
+
+use super::{Outage, Selector, UDP_PAYLOAD, dotted_name, expand_prefix, leaves, plain_number};
+use crate::config::StatsdConfig;
+use crate::router::stats_to_value;
+use crate::types::{StaticDeviceInfo, SystemStats};
+
+// ---------------------------------------------------------------------------
+// Exporter task — spawned once when `[statsd]` is enabled.
+// ---------------------------------------------------------------------------
+
+pub async fn run(
+    rx: watch::Receiver<SystemStats>,
+    config: StatsdConfig,
+    device: Arc<StaticDeviceInfo>,
+) {
+    let socket = match UdpSocket::bind("0.0.0.0:0").await {
+        Ok(socket) => socket,
+        Err(e) => {
+            eprintln!("statsd: udp socket: {e}");
+            return;
+        }
+    };
+    let prefix = expand_prefix(&config.prefix, &device);
+    let selector = Selector::new(&config.paths);
+    let mut outage = Outage::new("statsd");
+
+    let mut ticker = tokio::time::interval(config.interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        ticker.tick().await;
+        if rx.has_changed().is_err() {
+            break;
+        }
+        let tree = {
+            let stats = rx.borrow();
+            // Sequence 0 is the empty placeholder published before the first poll.
+            if stats.seq == 0 {
+                continue;
+            }
+            stats_to_value(&stats)
+        };
+
+        let gauges: Vec<String> = leaves(&tree)
+            .into_iter()
+            .filter(|(path, value)| value.is_finite() && selector.matches(path))
+            .map(|(path, value)| gauge(&dotted_name(&prefix, &path), value))
+            .collect();
+
+        match send(&socket, &config.address, &gauges).await {
+            Ok(()) => outage.recovered(),
+            Err(e) => outage.failed(e),
+        }
+    }
+}
+
+/// Pack whole gauges into datagrams of at most [`UDP_PAYLOAD`] bytes.  A
+/// gauge is never split, since UDP could reorder its reset and value lines.
+async fn send(socket: &UdpSocket, address: &str, gauges: &[String]) -> std::io::Result<()> {
+    let mut datagram = String::new();
+    for gauge in gauges {
+        if !datagram.is_empty() && datagram.len() + gauge.len() > UDP_PAYLOAD {
+            socket.send_to(datagram.as_bytes(), address).await?;
+            datagram.clear();
+        }
+        datagram.push_str(gauge);
+    }
+    if !datagram.is_empty() {
+        socket.send_to(datagram.as_bytes(), address).await?;
+    }
+    Ok(())
+}
+
+/// `name:value|g` lines for one gauge.  A leading `-` means "decrement" to
+/// StatsD, so a negative gauge is first reset to zero and then set relative
+/// to it.
+fn gauge(name: &str, value: f64) -> String {
+    let value = plain_number(value);
+    if value.starts_with('-') {
+        format!("{name}:0|g\n{name}:{value}|g\n")
+    } else {
+        format!("{name}:{value}|g\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_negative_gauge_is_reset_before_it_is_set() {
+        assert_eq!(gauge("asmo.cpu_temp", 41.5), "asmo.cpu_temp:41.5|g\n");
+        assert_eq!(gauge("asmo.offset", -3.0), "asmo.offset:0|g\nasmo.offset:-3|g\n");
+    }
+
+    #[tokio::test]
+    async fn send_keeps_each_reset_with_its_value() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = receiver.local_addr().unwrap().to_string();
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        // Enough negative gauges that several datagrams are needed.
+        let gauges: Vec<String> =
+            (0..200).map(|i| gauge(&format!("asmo.metric_{i:03}"), -1.5)).collect();
+        send(&socket, &address, &gauges).await.unwrap();
+
+        let mut received = String::new();
+        let mut datagrams = 0;
+        let mut buf = [0_u8; 2048];
+        while received.len() < gauges.concat().len() {
+            let len = receiver.recv(&mut buf).await.unwrap();
+            let datagram = std::str::from_utf8(&buf[..len]).unwrap();
+            assert!(len <= UDP_PAYLOAD);
+
+            let lines: Vec<&str> = datagram.lines().collect();
+            assert_eq!(lines.len() % 2, 0, "a gauge was split: {datagram}");
+            for pair in lines.chunks(2) {
+                let name = pair[0].strip_suffix(":0|g").unwrap();
+                assert_eq!(pair[1], format!("{name}:-1.5|g"));
+            }
+            received.push_str(datagram);
+            datagrams += 1;
+        }
+
+        assert!(datagrams > 1);
+        assert_eq!(received, gauges.concat());
+    }
+}
@@ -0,0 +1,106 @@
+//! Bounded in-memory history of past [`SystemStats`] snapshots.
+//!
+//! A recorder task subscribes to the monitor's watch channel and appends every
+//! published snapshot to a ring buffer sized from [`HistoryConfig`].  Once
+//! full, the oldest snapshot is dropped for each new one.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, RwLock};
+
+use tokio::sync::watch;
+
+use crate::clock::unix_millis;
+use crate::config::HistoryConfig;
+use crate::types::SystemStats;
+
+pub type SharedHistory = Arc<RwLock<History>>;
+
+/// A snapshot together with the Unix time (ms) it was published at.  The
+/// snapshot is shared so readers can take it out of the lock cheaply.
+pub struct Sample {
+    pub timestamp: u64,
+    pub stats: Arc<SystemStats>,
+}
+
+pub struct History {
+    samples: VecDeque<Sample>,
+    capacity: usize,
+    interval_ms: u64,
+}
+
+impl History {
+    pub fn new(config: &HistoryConfig) -> Self {
+        let interval_ms = (config.interval.as_millis() as u64).max(1);
+        let capacity = (config.retention.as_millis() as u64 / interval_ms).max(1) as usize;
+
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+            interval_ms,
+        }
+    }
+
+    /// Append a snapshot, skipping it if it arrives well before `interval`
+    /// has passed since the previous one.
+    pub fn push(&mut self, timestamp: u64, stats: Arc<SystemStats>) {
+        if let Some(last) = self.samples.back()
+            && timestamp.saturating_sub(last.timestamp) < self.interval_ms * 9 / 10
+        {
+            return;
+        }
+
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(Sample { timestamp, stats });
+    }
+
+    /// Timestamp of the oldest retained snapshot, if any.
+    pub fn oldest(&self) -> Option<u64> {
+        self.samples.front().map(|s| s.timestamp)
+    }
+
+    /// The retained snapshot with sequence number `seq`, if any.
+    pub fn get(&self, seq: u64) -> Option<&SystemStats> {
+        let i = self.samples.partition_point(|s| s.stats.seq < seq);
+        self.samples.get(i).map(|s| &*s.stats).filter(|stats| stats.seq == seq)
+    }
+
+    /// `select` applied to the snapshots published at or after `since` (Unix
+    /// ms), thinned so that consecutive results are at least `step_ms` apart.
+    /// Snapshots it returns `None` for are left out.
+    pub fn query<T>(
+        &self,
+        since: u64,
+        step_ms: u64,
+        mut select: impl FnMut(&Arc<SystemStats>) -> Option<T>,
+    ) -> Vec<(u64, T)> {
+        let start = self.samples.partition_point(|s| s.timestamp < since);
+        let mut next_due = 0_u64;
+
+        self.samples
+            .range(start..)
+            .filter(|s| {
+                if s.timestamp < next_due {
+                    return false;
+                }
+                next_due = s.timestamp + step_ms;
+                true
+            })
+            .filter_map(|s| Some((s.timestamp, select(&s.stats)?)))
+            .collect()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Recorder task — spawned once, runs until the monitor stops publishing.
+// ---------------------------------------------------------------------------
+
+pub async fn run_recorder(mut rx: watch::Receiver<SystemStats>, history: SharedHistory) {
+    while rx.changed().await.is_ok() {
+        let stats = Arc::new(rx.borrow_and_update().clone());
+        if let Ok(mut history) = history.write() {
+            history.push(unix_millis(), stats);
+        }
+    }
+}
@@ -1,26 +1,78 @@
+mod clock;
+mod condition;
+mod config;
 mod discover;
+mod encoding;
+mod export;
+mod history;
+mod meta;
+mod metrics;
 mod monitor;
 mod router;
+mod schema;
+mod store;
+mod summary;
 mod types;
+mod ws;
 
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use local_ip_address::local_ip;
 use tokio::net::TcpListener;
-use tokio::sync::watch;
+use tokio::sync::{broadcast, watch};
 
+use history::History;
+use router::AppState;
+use store::Store;
 use types::SystemStats;
 
 #[tokio::main]
 async fn main() {
-    let (paths, static_info) = discover::discover_device_layout();
+    let config = config::load();
+    let (paths, static_info) = discover::discover_device_layout(&config);
     let static_info = Arc::new(static_info);
+    let meta = Arc::new(meta::Registry::new());
 
     let (tx, rx) = watch::channel(SystemStats::default());
+    let (events, _) = broadcast::channel(64);
+    let history = Arc::new(RwLock::new(History::new(&config.history)));
 
-    tokio::spawn(monitor::run_monitor(tx, paths, Arc::clone(&static_info)));
+    tokio::spawn(monitor::run_monitor(
+        tx,
+        events.clone(),
+        paths,
+        Arc::clone(&static_info),
+    ));
 
-    let app = router::build(rx);
+    tokio::spawn(history::run_recorder(rx.clone(), Arc::clone(&history)));
+
+    let store = config.persist.enabled.then(|| {
+        Arc::new(Store::open(&config.persist).expect("failed to open history store"))
+    });
+    if let Some(store) = &store {
+        tokio::spawn(store::run_writer(rx.clone(), Arc::clone(store)));
+    }
+
+    if config.influx.enabled {
+        tokio::spawn(export::influx::run(rx.clone(), config.influx, Arc::clone(&static_info)));
+    }
+    if config.mqtt.enabled {
+        let device = Arc::clone(&static_info);
+        tokio::spawn(export::mqtt::run(rx.clone(), config.mqtt, device, Arc::clone(&meta)));
+    }
+    if config.statsd.enabled {
+        tokio::spawn(export::statsd::run(rx.clone(), config.statsd, Arc::clone(&static_info)));
+    }
+    if config.graphite.enabled {
+        let graphite = export::graphite::run(rx.clone(), config.graphite, Arc::clone(&static_info));
+        tokio::spawn(graphite);
+    }
+    if config.otlp.enabled {
+        let device = Arc::clone(&static_info);
+        tokio::spawn(export::otlp::run(rx.clone(), config.otlp, device, Arc::clone(&meta)));
+    }
+
+    let app = router::build(AppState { stats: rx, events, history, store, meta });
 
     let listener = TcpListener::bind("0.0.0.0:3000")
         .await
@@ -0,0 +1,226 @@
+//! Field metadata behind `/meta`: unit, description, source and refresh
+//! interval of anything the router can resolve.
+//!
+//! Everything comes from the generated schema (see [`crate::schema`]), so the
+//! single place to describe a field is its declaration in [`crate::types`]:
+//!
+//! ```text
+//! /// Current frequency.
+//! #[schemars(extend("x-unit" = "MHz", "x-source" = "…/scaling_cur_freq"))]
+//! pub cur_freq: f32,
+//! ```
+//!
+//! A test fails while any field lacks a description, `x-source` or
+//! `x-refresh`, so a new field can't ship without them.
+
+use serde_json::{Map, Value, json};
+
+use crate::router::{is_wildcard, split_predicate};
+use crate::schema::stats_schema;
+
+/// What a dashboard needs to know to display one field.
+pub struct FieldMeta {
+    pub unit: Option<String>,
+    pub description: String,
+    pub source: String,
+    /// `500ms`, `30s` or `static`.
+    pub refresh: String,
+}
+
+impl FieldMeta {
+    pub fn is_static(&self) -> bool {
+        self.refresh == "static"
+    }
+}
+
+/// The `/stats` schema, navigable by router path.
+pub struct Registry {
+    schema: Value,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self { schema: stats_schema() }
+    }
+
+    /// Metadata of the field at `segments`; array items are addressed by
+    /// name or wildcard, as in the router.
+    pub fn field(&self, segments: &[&str]) -> Option<FieldMeta> {
+        let (node, inherited) = self.navigate(segments)?;
+        Some(self.meta(node, &inherited))
+    }
+
+    /// Whether the field at `segments` is an array.
+    pub fn is_array(&self, segments: &[&str]) -> bool {
+        self.navigate(segments).is_some_and(|(node, _)| {
+            self.deref(node).get("type").and_then(Value::as_str) == Some("array")
+        })
+    }
+
+    /// `/meta/<path>` response: the field's metadata, with `fields` for
+    /// objects and arrays of objects.  A comma list in the last segment gives
+    /// an object keyed by field instead.
+    pub fn describe(&self, segments: &[&str]) -> Option<Value> {
+        if let Some((last, parent)) = segments.split_last()
+            && last.contains(',')
+        {
+            let mut out = Map::new();
+            for field in last.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+                let mut path = parent.to_vec();
+                path.push(field);
+                let (node, inherited) = self.navigate(&path)?;
+                out.insert(field.to_owned(), self.describe_node(node, &inherited));
+            }
+            return Some(Value::Object(out));
+        }
+
+        let (node, inherited) = self.navigate(segments)?;
+        Some(self.describe_node(node, &inherited))
+    }
+
+    // ── Schema navigation ──────────────────────────────────────────────
+
+    /// Follow `segments` from the root.  Returns the schema of the property
+    /// reached and the `x-source` / `x-refresh` in effect there, which array
+    /// items take from the array field that holds them.
+    fn navigate(&self, segments: &[&str]) -> Option<(&Value, Inherited)> {
+        let mut node = &self.schema;
+        let mut inherited = Inherited::default().under(node);
+
+        for segment in segments {
+            // A predicate (`cores[usage>50]`) is the array field and a
+            // wildcard in one.
+            let (field, predicate) = match split_predicate(segment) {
+                Some((field, _)) => (field, true),
+                None => (*segment, false),
+            };
+            let target = self.deref(node);
+            node = match target.get("type").and_then(Value::as_str) {
+                // Any item name (or wildcard) selects the item schema.
+                Some("array") => target.get("items")?,
+                _ if is_wildcard(field) => return None,
+                _ => target.get("properties")?.get(field)?,
+            };
+            inherited = inherited.under(node).under(self.deref(node));
+
+            if predicate {
+                node = self.deref(node).get("items")?;
+                inherited = inherited.under(node).under(self.deref(node));
+            }
+        }
+        Some((node, inherited))
+    }
+
+    /// The definition a `$ref` points to, or `node` itself.
+    fn deref<'a>(&'a self, node: &'a Value) -> &'a Value {
+        node.get("$ref")
+            .and_then(Value::as_str)
+            .and_then(|r| r.strip_prefix("#/$defs/"))
+            .and_then(|name| self.schema.get("$defs")?.get(name))
+            .unwrap_or(node)
+    }
+
+    fn meta(&self, node: &Value, inherited: &Inherited) -> FieldMeta {
+        let text = |key: &str| {
+            node.get(key)
+                .or_else(|| self.deref(node).get(key))
+                .and_then(Value::as_str)
+                .map(str::to_owned)
+        };
+        FieldMeta {
+            unit: text("x-unit"),
+            description: text("description").unwrap_or_default(),
+            source: text("x-source").or_else(|| inherited.source.clone()).unwrap_or_default(),
+            refresh: text("x-refresh").or_else(|| inherited.refresh.clone()).unwrap_or_default(),
+        }
+    }
+
+    fn describe_node(&self, node: &Value, inherited: &Inherited) -> Value {
+        let meta = self.meta(node, inherited);
+        let target = self.deref(node);
+        let mut out = json!({
+            "type": target.get("type").cloned().unwrap_or(Value::Null),
+            "unit": meta.unit,
+            "description": meta.description,
+            "source": meta.source,
+            "refresh": meta.refresh,
+            "static": meta.is_static(),
+        });
+        if let Some(values) = target.get("enum") {
+            out["values"] = values.clone();
+        }
+
+        // Objects list their properties; arrays list their items'.
+        let inherited = inherited.under(node).under(target);
+        let fields = match target.get("items") {
+            Some(items) => self.deref(items).get("properties"),
+            None => target.get("properties"),
+        };
+        if let Some(Value::Object(properties)) = fields {
+            let fields: Map<String, Value> = properties
+                .iter()
+                .map(|(key, child)| (key.clone(), self.describe_node(child, &inherited)))
+                .collect();
+            out["fields"] = Value::Object(fields);
+        }
+        out
+    }
+}
+
+/// Source and refresh interval handed down from enclosing fields.
+#[derive(Clone, Default)]
+struct Inherited {
+    source: Option<String>,
+    refresh: Option<String>,
+}
+
+impl Inherited {
+    /// These defaults, overridden by whatever `node` sets itself.
+    fn under(&self, node: &Value) -> Self {
+        let text = |key: &str| node.get(key).and_then(Value::as_str).map(str::to_owned);
+        Self {
+            source: text("x-source").or_else(|| self.source.clone()),
+            refresh: text("x-refresh").or_else(|| self.refresh.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `Type.field` of every property without a description, source or
+    /// refresh interval.
+    fn missing(schema: &Value) -> Vec<String> {
+        let title = schema.get("title").and_then(Value::as_str).unwrap_or("SystemStats");
+        let mut types = vec![(title, schema)];
+        if let Some(Value::Object(defs)) = schema.get("$defs") {
+            types.extend(defs.iter().map(|(name, def)| (name.as_str(), def)));
+        }
+
+        let mut missing = Vec::new();
+        for (name, def) in types {
+            let Some(Value::Object(properties)) = def.get("properties") else { continue };
+            for (field, property) in properties {
+                let described = ["description", "x-source", "x-refresh"]
+                    .iter()
+                    .all(|key| property.get(*key).is_some_and(|v| v.as_str() != Some("")));
+                if !described {
+                    missing.push(format!("{name}.{field}"));
+                }
+            }
+        }
+        missing
+    }
+
+    #[test]
+    fn every_field_has_metadata() {
+        let missing = missing(&Registry::new().schema);
+        assert!(
+            missing.is_empty(),
+            "fields without metadata — give each a doc comment and `x-source` / `x-refresh` \
+             (directly or on its struct) in types.rs: {}",
+            missing.join(", ")
+        );
+    }
+}
@@ -0,0 +1,296 @@
+//! Prometheus / OpenMetrics exposition of the stats tree behind `/metrics`.
+//!
+//! The serialized [`SystemStats`](crate::types::SystemStats) tree is walked
+//! generically, so new fields show up without changes here:
+//!
+//! - numeric and boolean fields of every [`Row`](crate::export::Row) become
+//!   gauges: `asmo_cpu_temp`, `asmo_core_usage{core="cpu0",model="Cortex-A510"}`
+//! - enum fields become state sets (`asmo_battery_status{asmo_battery_status="Full"} 1`),
+//!   array items' ones labeled like their gauges, so a sensor changing status
+//!   doesn't start a new series
+//! - identity strings and the `device` object form the `asmo_device_info`
+//!   info metric, and the foreground app `asmo_foreground_info`
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use serde_json::{Map, Value};
+
+use crate::export::{Labels, label_value, rows, states};
+
+const PREFIX: &str = "asmo";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Prometheus,
+    OpenMetrics,
+}
+
+impl Format {
+    /// OpenMetrics when the scraper lists it in `Accept`, Prometheus text otherwise.
+    pub fn negotiate(accept: Option<&str>) -> Self {
+        match accept {
+            Some(accept) if accept.contains("application/openmetrics-text") => Self::OpenMetrics,
+            _ => Self::Prometheus,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Prometheus => "text/plain; version=0.0.4; charset=utf-8",
+            Self::OpenMetrics => "application/openmetrics-text; version=1.0.0; charset=utf-8",
+        }
+    }
+}
+
+/// Render a stats value tree (see [`crate::router::stats_to_value`]).
+pub fn render(tree: &Value, format: Format) -> String {
+    let mut exposition = Exposition::default();
+    for row in rows(tree) {
+        for (field, value) in &row.fields {
+            let name = row.metric(PREFIX, field, "_");
+            exposition.push(&name, Kind::Gauge, row.labels.clone(), value);
+        }
+        for (field, current) in &row.states {
+            let name = row.metric(PREFIX, field, "_");
+            let all = states(&row.metric("", field, "_"));
+            // A field without a known state list reports only its current state.
+            for state in all.unwrap_or_else(|| vec![current.clone()]) {
+                let mut labels = row.labels.clone();
+                let active = (state == *current) as u8;
+                labels.push((name.clone(), state));
+                exposition.push(&name, Kind::StateSet, labels, active);
+            }
+        }
+    }
+    if let Value::Object(map) = tree {
+        walk_root(&mut exposition, map);
+    }
+    exposition.render(format)
+}
+
+// ---------------------------------------------------------------------------
+// Root strings — state sets and info metrics
+// ---------------------------------------------------------------------------
+
+fn walk_root(out: &mut Exposition, map: &Map<String, Value>) {
+    let mut device = Labels::new();
+    let mut foreground = Labels::new();
+
+    for (key, value) in map {
+        match value {
+            Value::String(current) => {
+                if let Some(states) = states(key) {
+                    let name = format!("{PREFIX}_{key}");
+                    for state in states {
+                        let active = (state == *current) as u8;
+                        out.push(&name, Kind::StateSet, vec![(name.clone(), state)], active);
+                    }
+                } else if let Some(field) = key.strip_prefix("foreground_") {
+                    foreground.push((field.to_owned(), current.clone()));
+                } else {
+                    device.push((key.clone(), current.clone()));
+                }
+            }
+            Value::Object(fields) if key == "device" => {
+                for (field, value) in fields {
+                    if let Some(text) = label_value(value) {
+                        device.push((field.clone(), text));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    out.push(&format!("{PREFIX}_device"), Kind::Info, device, 1);
+    out.push(&format!("{PREFIX}_foreground"), Kind::Info, foreground, 1);
+}
+
+// ---------------------------------------------------------------------------
+// Exposition — metric families in first-seen order
+// ---------------------------------------------------------------------------
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Gauge,
+    StateSet,
+    Info,
+}
+
+struct Family {
+    name: String,
+    kind: Kind,
+    samples: Vec<(Labels, String)>,
+}
+
+#[derive(Default)]
+struct Exposition {
+    families: Vec<Family>,
+    index: HashMap<String, usize>,
+}
+
+impl Exposition {
+    fn push(&mut self, name: &str, kind: Kind, labels: Labels, value: impl ToString) {
+        let i = *self.index.entry(name.to_owned()).or_insert_with(|| {
+            self.families.push(Family { name: name.to_owned(), kind, samples: Vec::new() });
+            self.families.len() - 1
+        });
+        self.families[i].samples.push((labels, value.to_string()));
+    }
+
+    fn render(&self, format: Format) -> String {
+        let mut out = String::new();
+
+        for family in &self.families {
+            // Info metrics are `<name>_info` samples; OpenMetrics types the
+            // family by its bare name, plain Prometheus knows only gauges.
+            let sample_name = match family.kind {
+                Kind::Info => format!("{}_info", family.name),
+                _ => family.name.clone(),
+            };
+            let (type_name, type_label) = match (format, family.kind) {
+                (Format::OpenMetrics, Kind::Info) => (family.name.as_str(), "info"),
+                (Format::OpenMetrics, Kind::StateSet) => (family.name.as_str(), "stateset"),
+                _ => (sample_name.as_str(), "gauge"),
+            };
+            let _ = writeln!(out, "# TYPE {type_name} {type_label}");
+
+            for (labels, value) in &family.samples {
+                out.push_str(&sample_name);
+                if !labels.is_empty() {
+                    out.push('{');
+                    for (i, (key, value)) in labels.iter().enumerate() {
+                        if i > 0 {
+                            out.push(',');
+                        }
+                        let _ = write!(out, "{}=\"{}\"", sanitize(key), escape(value));
+                    }
+                    out.push('}');
+                }
+                let _ = writeln!(out, " {value}");
+            }
+        }
+
+        if format == Format::OpenMetrics {
+            out.push_str("# EOF\n");
+        }
+        out
+    }
+}
+
+/// Make a field name a valid label name (`[a-zA-Z_][a-zA-Z0-9_]*`).
+fn sanitize(name: &str) -> String {
+    name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect()
+}
+
+/// Escape a label value per the text exposition format.
+fn escape(value: &str) -> String {
+    value.replace('\\', r"\\").replace('"', "\\\"").replace('\n', r"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn fixture() -> Value {
+        json!({
+            "battery_level": 81,
+            "screen_on": true,
+            "battery_status": "Full",
+            "kernel_version": "6.1.0",
+            "foreground_package": "com.example",
+            "device": { "codename": "taro", "abis": ["arm64-v8a", "armeabi-v7a"] },
+            "clusters": [{
+                "cores": ["cpu0", "cpu1"],
+                "time_in_state": [{ "freq": 300, "residency": 12.5 }],
+            }],
+            "storage": [{
+                "name": "sdcard",
+                "mount_point": "/storage/emulated/0",
+                "free_gb": 84.5,
+            }],
+            "hal_temperatures": [{
+                "name": "skin",
+                "type": "SKIN",
+                "value": 33.5,
+                "status": "Light",
+            }],
+        })
+    }
+
+    #[test]
+    fn renders_prometheus_text() {
+        let expected = r#"# TYPE asmo_battery_level gauge
+asmo_battery_level 81
+# TYPE asmo_screen_on gauge
+asmo_screen_on 1
+# TYPE asmo_cluster_time_in_state_residency gauge
+asmo_cluster_time_in_state_residency{cores="cpu0,cpu1",freq="300"} 12.5
+# TYPE asmo_volume_free_gb gauge
+asmo_volume_free_gb{volume="sdcard",mount_point="/storage/emulated/0"} 84.5
+# TYPE asmo_hal_temperature gauge
+asmo_hal_temperature{hal_temperature="skin",type="SKIN"} 33.5
+# TYPE asmo_hal_temperature_status gauge
+asmo_hal_temperature_status{hal_temperature="skin",type="SKIN",asmo_hal_temperature_status="None"} 0
+asmo_hal_temperature_status{hal_temperature="skin",type="SKIN",asmo_hal_temperature_status="Light"} 1
+asmo_hal_temperature_status{hal_temperature="skin",type="SKIN",asmo_hal_temperature_status="Moderate"} 0
+asmo_hal_temperature_status{hal_temperature="skin",type="SKIN",asmo_hal_temperature_status="Severe"} 0
+asmo_hal_temperature_status{hal_temperature="skin",type="SKIN",asmo_hal_temperature_status="Critical"} 0
+asmo_hal_temperature_status{hal_temperature="skin",type="SKIN",asmo_hal_temperature_status="Emergency"} 0
+asmo_hal_temperature_status{hal_temperature="skin",type="SKIN",asmo_hal_temperature_status="Shutdown"} 0
+asmo_hal_temperature_status{hal_temperature="skin",type="SKIN",asmo_hal_temperature_status="N/A"} 0
+# TYPE asmo_battery_status gauge
+asmo_battery_status{asmo_battery_status="Charging"} 0
+asmo_battery_status{asmo_battery_status="Discharging"} 0
+asmo_battery_status{asmo_battery_status="Not Charging"} 0
+asmo_battery_status{asmo_battery_status="Full"} 1
+asmo_battery_status{asmo_battery_status="N/A"} 0
+# TYPE asmo_device_info gauge
+asmo_device_info{kernel_version="6.1.0",codename="taro",abis="arm64-v8a,armeabi-v7a"} 1
+# TYPE asmo_foreground_info gauge
+asmo_foreground_info{package="com.example"} 1
+"#;
+        assert_eq!(render(&fixture(), Format::Prometheus), expected);
+    }
+
+    #[test]
+    fn openmetrics_types_state_sets_and_info_metrics() {
+        let prometheus = render(&fixture(), Format::Prometheus);
+        let openmetrics = render(&fixture(), Format::OpenMetrics);
+
+        let types: Vec<&str> = openmetrics.lines().filter(|l| l.starts_with("# ")).collect();
+        assert_eq!(
+            types,
+            [
+                "# TYPE asmo_battery_level gauge",
+                "# TYPE asmo_screen_on gauge",
+                "# TYPE asmo_cluster_time_in_state_residency gauge",
+                "# TYPE asmo_volume_free_gb gauge",
+                "# TYPE asmo_hal_temperature gauge",
+                "# TYPE asmo_hal_temperature_status stateset",
+                "# TYPE asmo_battery_status stateset",
+                "# TYPE asmo_device info",
+                "# TYPE asmo_foreground info",
+                "# EOF",
+            ]
+        );
+
+        // Samples are the same in both formats.
+        let samples = |text: &str| -> Vec<String> {
+            text.lines().filter(|l| !l.starts_with('#')).map(str::to_owned).collect()
+        };
+        assert_eq!(samples(&openmetrics), samples(&prometheus));
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        let tree = json!({ "disks": [{ "name": "a\"b\\c\nd", "utilization": 1 }] });
+        assert_eq!(
+            render(&tree, Format::Prometheus).lines().nth(1),
+            Some(r#"asmo_disk_utilization{disk="a\"b\\c\nd"} 1"#)
+        );
+    }
+}
@@ -2,16 +2,31 @@ use std::ffi::CString;
 use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::process::{Command, Stdio};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use tokio::sync::watch;
+use tokio::sync::{broadcast, watch};
 
+use crate::clock::{read_clock, unix_millis};
 use crate::types::{
-    BatteryStatus, CoreData, CpuSnap, DevicePaths, StaticDeviceInfo, SystemStats,
+    AppSwitch, BatteryStatus, ClusterData, CoreData, CpuSnap, DevicePaths, DiskData, DiskSnap,
+    DisplayState, DozeMode, FreqResidency, HalTemperature, IdleSnap, IdleStateData,
+    StaticCoreInfo, StaticDeviceInfo, StorageData, SystemStats, TemperatureType, ThermalStatus,
 };
 
 const POLL_INTERVAL: Duration = Duration::from_millis(500);
 const STORAGE_TICK_INTERVAL: u64 = 60; // 60 ticks = 30 s
+const SECTOR_BYTES: f32 = 512.0;
+
+/// Which part of the rish batch output is currently being parsed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Normal,
+    Display,
+    Diskstats,
+    Thermal,
+    Power,
+    Foreground,
+}
 
 // ---------------------------------------------------------------------------
 // Hot monitoring loop — spawned once, runs forever.
@@ -19,18 +34,36 @@ const STORAGE_TICK_INTERVAL: u64 = 60; // 60 ticks = 30 s
 
 pub async fn run_monitor(
     tx: watch::Sender<SystemStats>,
+    events: broadcast::Sender<AppSwitch>,
     paths: DevicePaths,
     static_info: Arc<StaticDeviceInfo>,
 ) {
     let core_len = static_info.cores.len();
+    let disk_len = paths.block_devices.len();
 
-    // Rish batch — runs every tick (cpu + battery + display).
-    let fast_cmd = b"echo UPTIME $(cat /proc/uptime); \
-                     cat /proc/stat; \
+    // Rish batch — runs every tick (cpu + battery + display + disk I/O + thermal HAL
+    // + power / Doze state + foreground app).  `dumpsys window` is only a fallback
+    // for builds whose activity dump lacks a resumed-activity line.
+    let fast_cmd = b"cat /proc/stat; \
                      dumpsys battery | grep -E 'level|status|temp'; \
                      echo DISPLAY_DATA; \
                      dumpsys display | grep -oE 'mBrightness=[0-9.]+|mActiveRenderFrameRate=[0-9.]+'; \
                      echo DISPLAY_END; \
+                     echo DISKSTATS_DATA; \
+                     cat /proc/diskstats; \
+                     echo DISKSTATS_END; \
+                     echo THERMAL_DATA; \
+                     dumpsys thermalservice | grep -E '^Thermal Status|^Current temperatures|Temperature\\{'; \
+                     echo THERMAL_END; \
+                     echo POWER_DATA; \
+                     dumpsys power | grep -E 'mWakefulness=|Display Power: state='; \
+                     echo low_power=$(settings get global low_power); \
+                     dumpsys deviceidle | grep -m1 'mState='; \
+                     echo POWER_END; \
+                     echo FOREGROUND_DATA; \
+                     fg=$(dumpsys activity activities | grep -m1 -E 'topResumedActivity=|mResumedActivity'); \
+                     [ -n \"$fg\" ] && echo \"$fg\" || dumpsys window | grep -m1 mCurrentFocus; \
+                     echo FOREGROUND_END; \
                      echo 'END_OF_BATCH'\n";
 
     // Slow rish batch — same as fast (refresh rate changes rarely but comes for free).
@@ -55,14 +88,30 @@ pub async fn run_monitor(
     // Pre-allocated scratch space — reused every tick.
     let mut core_snaps: Vec<CpuSnap> = (0..core_len).map(|_| CpuSnap::default()).collect();
     let mut core_usages = vec![0.0_f32; core_len];
+    // `None` until a device's first reading, which only sets the baseline.
+    let mut disk_snaps: Vec<Option<DiskSnap>> = (0..disk_len).map(|_| None).collect();
+    let mut idle_snaps: Vec<Vec<Option<IdleSnap>>> = static_info
+        .cores
+        .iter()
+        .map(|c| vec![None; c.idle_states.len()])
+        .collect();
+    let mut freq_snaps: Vec<Vec<u64>> = vec![Vec::new(); static_info.clusters.len()];
+    let mut last_batch = Instant::now();
 
     // Slow-tick cached state — retained between iterations.
     let mut tick: u64 = 0;
     let mut cached_storage_free_gb = 0.0_f32;
     let mut cached_storage_total_gb = 0.0_f32;
+    let mut cached_storage: Vec<StorageData> = Vec::with_capacity(paths.storage.len());
+    let mut foreground_package: Arc<str> = Arc::from("");
+    let mut foreground_activity: Arc<str> = Arc::from("");
 
     loop {
-        let is_storage_tick = tick % STORAGE_TICK_INTERVAL == 0;
+        let is_storage_tick = tick.is_multiple_of(STORAGE_TICK_INTERVAL);
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(last_batch).as_secs_f32();
+        last_batch = now;
 
         // ── Direct sysfs/procfs reads (no privilege needed) ──────────
         let cpu_temp = read_sysfs_thermal(&paths.cpu_temp);
@@ -70,12 +119,59 @@ pub async fn run_monitor(
         let gpu_load = read_gpu_load();
         let (memory_total_mb, memory_avail_mb, swap_total_mb, swap_free_mb) = read_memory();
         let cur_freqs = read_cpu_freqs(core_len);
+        let idle_states: Vec<Vec<IdleStateData>> = static_info
+            .cores
+            .iter()
+            .zip(idle_snaps.iter_mut())
+            .map(|(info, snaps)| read_idle_residency(info, snaps, elapsed))
+            .collect();
+        let clusters: Vec<ClusterData> = static_info
+            .clusters
+            .iter()
+            .zip(freq_snaps.iter_mut())
+            .map(|(info, snaps)| ClusterData {
+                name: Arc::clone(&info.name),
+                cores: Arc::clone(&info.cores),
+                time_in_state: read_time_in_state(&info.name, snaps),
+            })
+            .collect();
+        let (load_avg_1m, load_avg_5m, load_avg_15m, running_tasks, total_tasks) =
+            read_loadavg();
+
+        // Suspend time is the only thing CLOCK_BOOTTIME counts that
+        // CLOCK_MONOTONIC does not.
+        let boot_clock = read_clock(libc::CLOCK_BOOTTIME);
+        let awake_clock = read_clock(libc::CLOCK_MONOTONIC);
+        let uptime_seconds = boot_clock as u64;
+        let awake_seconds = awake_clock as u64;
+        let deep_sleep_seconds = (boot_clock - awake_clock).max(0.0) as u64;
 
         // Slow direct reads.
         if is_storage_tick {
-            let (free, total) = read_storage();
-            cached_storage_free_gb = free;
-            cached_storage_total_gb = total;
+            cached_storage.clear();
+            cached_storage.extend(paths.storage.iter().map(|mount| {
+                let (free_gb, total_gb, inodes_free, inodes_total) =
+                    read_mount_usage(&mount.mount_point);
+                StorageData {
+                    name: Arc::clone(&mount.name),
+                    mount_point: Arc::clone(&mount.mount_point),
+                    free_gb,
+                    total_gb,
+                    inodes_free,
+                    inodes_total,
+                }
+            }));
+
+            // The top-level totals are the `/data` entry's, read on its own
+            // only when `[storage] mounts` leaves it out.
+            let data = cached_storage.iter().find(|mount| &*mount.mount_point == "/data");
+            (cached_storage_free_gb, cached_storage_total_gb) = match data {
+                Some(data) => (data.free_gb, data.total_gb),
+                None => {
+                    let (free, total, _, _) = read_mount_usage("/data");
+                    (free, total)
+                }
+            };
         }
 
         // ── Privileged reads via rish ────────────────────────────────
@@ -87,11 +183,20 @@ pub async fn run_monitor(
 
         core_usages.iter_mut().for_each(|u| *u = 0.0);
 
+        let mut disks: Vec<DiskData> = Vec::with_capacity(disk_len);
+
         let mut battery_temp = 0.0_f32;
         let mut battery_level = 0_i32;
         let mut battery_status = BatteryStatus::Unknown;
-        let mut uptime_seconds = 0_u64;
-        let mut in_display_section = false;
+        let mut section = Section::Normal;
+        let mut thermal_status = ThermalStatus::Unknown;
+        let mut hal_temperatures: Vec<HalTemperature> = Vec::new();
+        let mut in_hal_temperatures = false;
+        let mut display_state = DisplayState::Unknown;
+        let mut interactive = false;
+        let mut battery_saver = false;
+        let mut device_idle = DozeMode::Unknown;
+        let mut foreground: Option<(String, String)> = None;
         let mut brightness = 0.0_f32;
         let mut refresh_rate = 0.0_f32;
         let mut brightness_found = false;
@@ -105,43 +210,118 @@ pub async fn run_monitor(
             }
 
             // ── Section markers ──────────────────────────────────────
-            if line == "DISPLAY_DATA" {
-                in_display_section = true;
-                continue;
-            }
-            if line == "DISPLAY_END" {
-                in_display_section = false;
-                continue;
+            match line {
+                "DISPLAY_DATA" => {
+                    section = Section::Display;
+                    continue;
+                }
+                "DISKSTATS_DATA" => {
+                    section = Section::Diskstats;
+                    continue;
+                }
+                "THERMAL_DATA" => {
+                    section = Section::Thermal;
+                    continue;
+                }
+                "POWER_DATA" => {
+                    section = Section::Power;
+                    continue;
+                }
+                "FOREGROUND_DATA" => {
+                    section = Section::Foreground;
+                    continue;
+                }
+                "DISPLAY_END" | "DISKSTATS_END" | "THERMAL_END" | "POWER_END"
+                | "FOREGROUND_END" => {
+                    section = Section::Normal;
+                    continue;
+                }
+                _ => {}
             }
 
-
             // ── Display section ──────────────────────────────────────
-            if in_display_section {
+            if section == Section::Display {
                 if let Some(val) = line.strip_prefix("mBrightness=") {
                     if !brightness_found {
                         brightness = val.parse().unwrap_or(0.0);
                         brightness_found = true;
                     }
-                } else if let Some(val) = line.strip_prefix("mActiveRenderFrameRate=") {
-                    if !refresh_rate_found {
-                        refresh_rate = val.parse().unwrap_or(0.0);
-                        refresh_rate_found = true;
+                } else if let Some(val) = line.strip_prefix("mActiveRenderFrameRate=")
+                    && !refresh_rate_found
+                {
+                    refresh_rate = val.parse().unwrap_or(0.0);
+                    refresh_rate_found = true;
+                }
+                continue;
+            }
+
+            // ── Diskstats section ────────────────────────────────────
+            if section == Section::Diskstats {
+                let mut it = line.split_whitespace().skip(2);
+                let Some(dev) = it.next() else { continue };
+                let Some(idx) = paths.block_devices.iter().position(|d| &**d == dev) else {
+                    continue;
+                };
+                let snap = parse_diskstat(it);
+                let prev = disk_snaps[idx].as_ref();
+                disks.push(disk_rates(&paths.block_devices[idx], prev, &snap, elapsed));
+                disk_snaps[idx] = Some(snap);
+                continue;
+            }
+
+            // ── Thermal service section ──────────────────────────────
+            // Temperatures are listed twice (cached, then live from the HAL);
+            // only the live block that follows the header is used.
+            if section == Section::Thermal {
+                if let Some(code) = line.strip_prefix("Thermal Status:") {
+                    thermal_status = ThermalStatus::from_code(code.trim().parse().unwrap_or(-1));
+                } else if line.starts_with("Current temperatures") {
+                    in_hal_temperatures = true;
+                } else if in_hal_temperatures
+                    && let Some(temp) = parse_hal_temperature(line)
+                {
+                    hal_temperatures.push(temp);
+                }
+                continue;
+            }
+
+            // ── Power section ────────────────────────────────────────
+            if section == Section::Power {
+                if let Some(val) = line.strip_prefix("mWakefulness=") {
+                    // Mirrors PowerManagerInternal.isInteractive().
+                    interactive = matches!(val, "Awake" | "Dreaming");
+                } else if let Some(val) = line.strip_prefix("Display Power: state=") {
+                    display_state = DisplayState::from_name(val);
+                } else if let Some(val) = line.strip_prefix("low_power=") {
+                    battery_saver = val == "1";
+                } else if line.starts_with("mState=") {
+                    // `mState=IDLE mLightState=OVERRIDE`
+                    let mut deep = "";
+                    let mut light = "";
+                    for tok in line.split_whitespace() {
+                        if let Some(v) = tok.strip_prefix("mState=") {
+                            deep = v;
+                        } else if let Some(v) = tok.strip_prefix("mLightState=") {
+                            light = v;
+                        }
                     }
+                    device_idle = DozeMode::from_states(deep, light);
+                }
+                continue;
+            }
+
+            // ── Foreground section ───────────────────────────────────
+            if section == Section::Foreground {
+                if foreground.is_none() {
+                    foreground = parse_foreground(line);
                 }
                 continue;
             }
 
-            // ── Normal section (uptime / cpu / battery) ──────────────
+            // ── Normal section (cpu / battery) ───────────────────────
             let (tag, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
 
             match tag {
-                "UPTIME" => {
-                    uptime_seconds = rest
-                        .split_whitespace()
-                        .next()
-                        .and_then(|v| v.parse::<f32>().ok())
-                        .unwrap_or(0.0) as u64;
-                }
                 "cpu" => { /* aggregate line — skip */ }
                 "level:" => battery_level = rest.trim().parse().unwrap_or(0),
                 "status:" => {
@@ -169,34 +349,68 @@ pub async fn run_monitor(
             }
         }
 
+        // Keep the previous app when nothing was parsed (e.g. mid-transition),
+        // and only allocate new strings when it actually changed.
+        if let Some((package, activity)) = foreground
+            && (*foreground_package != *package || *foreground_activity != *activity)
+        {
+            let to_package: Arc<str> = Arc::from(package);
+            let to_activity: Arc<str> = Arc::from(activity);
+            if !foreground_package.is_empty() {
+                let _ = events.send(AppSwitch {
+                    timestamp: unix_millis(),
+                    from_package: Arc::clone(&foreground_package),
+                    from_activity: Arc::clone(&foreground_activity),
+                    to_package: Arc::clone(&to_package),
+                    to_activity: Arc::clone(&to_activity),
+                });
+            }
+            foreground_package = to_package;
+            foreground_activity = to_activity;
+        }
+
         // Build the payload — Arc clones are just atomic increments.
         let cores: Vec<CoreData> = static_info
             .cores
             .iter()
+            .zip(idle_states)
             .enumerate()
-            .map(|(i, info)| CoreData {
+            .map(|(i, (info, idle_states))| CoreData {
                 name: Arc::clone(&info.name),
                 usage: core_usages.get(i).copied().unwrap_or(0.0),
                 model_name: Arc::clone(&info.model_name),
                 cur_freq: cur_freqs.get(i).copied().unwrap_or(0.0),
                 min_freq: info.min_freq,
                 max_freq: info.max_freq,
+                idle_states,
             })
             .collect();
 
         let stats = SystemStats {
+            seq: tick + 1,
             manufacturer: Arc::clone(&static_info.manufacturer),
             product_model: Arc::clone(&static_info.product_model),
             soc_model: Arc::clone(&static_info.soc_model),
             kernel_version: Arc::clone(&static_info.kernel_version),
             android_version: Arc::clone(&static_info.android_version),
+            device: Arc::clone(&static_info.device),
             uptime_seconds,
+            awake_seconds,
+            deep_sleep_seconds,
+            boot_time: static_info.boot_time,
+            load_avg_1m,
+            load_avg_5m,
+            load_avg_15m,
+            running_tasks,
+            total_tasks,
             battery_level,
             battery_status,
             battery_temp,
             cpu_temp,
             gpu_temp,
             gpu_load,
+            thermal_status,
+            hal_temperatures,
             memory_used_mb: (memory_total_mb - memory_avail_mb).max(0.0),
             memory_total_mb,
             swap_used_mb: (swap_total_mb - swap_free_mb).max(0.0),
@@ -205,7 +419,17 @@ pub async fn run_monitor(
             storage_total_gb: cached_storage_total_gb,
             refresh_rate,
             brightness,
+            screen_on: display_state.is_on(),
+            display_state,
+            interactive,
+            battery_saver,
+            device_idle,
+            foreground_package: Arc::clone(&foreground_package),
+            foreground_activity: Arc::clone(&foreground_activity),
             cores,
+            clusters,
+            storage: cached_storage.clone(),
+            disks,
         };
 
         let _ = tx.send(stats);
@@ -266,6 +490,22 @@ fn read_memory() -> (f32, f32, f32, f32) {
     (total, avail, swap_total, swap_free)
 }
 
+/// Read `/proc/loadavg`.
+/// Returns (1 min, 5 min, 15 min, running tasks, total tasks).
+#[inline]
+fn read_loadavg() -> (f32, f32, f32, u32, u32) {
+    let content = std::fs::read_to_string("/proc/loadavg").unwrap_or_default();
+    let mut it = content.split_whitespace();
+    let mut load = || it.next().and_then(|v| v.parse().ok()).unwrap_or(0.0);
+    let (one, five, fifteen) = (load(), load(), load());
+    let (running, total) = it
+        .next()
+        .and_then(|v| v.split_once('/'))
+        .map(|(r, t)| (r.parse().unwrap_or(0), t.parse().unwrap_or(0)))
+        .unwrap_or((0, 0));
+    (one, five, fifteen, running, total)
+}
+
 /// Read current frequency for each core from sysfs, returns MHz.
 fn read_cpu_freqs(count: usize) -> Vec<f32> {
     (0..count)
@@ -281,20 +521,94 @@ fn read_cpu_freqs(count: usize) -> Vec<f32> {
         .collect()
 }
 
-/// Read storage free/total for `/data` via `statvfs`.
-/// Returns (free_gb, total_gb).
-#[inline]
-fn read_storage() -> (f32, f32) {
-    let path = CString::new("/data").unwrap();
+/// Read cumulative cpuidle counters for one core and turn them into the
+/// percentage of `elapsed` spent in each state, updating `snaps` in place.
+/// A state's first reading only sets the baseline.
+fn read_idle_residency(
+    info: &StaticCoreInfo,
+    snaps: &mut [Option<IdleSnap>],
+    elapsed: f32,
+) -> Vec<IdleStateData> {
+    let dir = format!("/sys/devices/system/cpu/{}/cpuidle", info.name);
+    let read = |state: usize, file: &str| -> u64 {
+        std::fs::read_to_string(format!("{dir}/state{state}/{file}"))
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(0)
+    };
+
+    info.idle_states
+        .iter()
+        .zip(snaps.iter_mut())
+        .enumerate()
+        .map(|(i, (name, snap))| {
+            let cur = IdleSnap { time_us: read(i, "time"), usage: read(i, "usage") };
+            let (residency, entries) = match snap {
+                Some(prev) if elapsed > 0.0 => {
+                    let dt = cur.time_us.saturating_sub(prev.time_us) as f32;
+                    (
+                        (dt / (elapsed * 1_000_000.0) * 100.0).min(100.0),
+                        cur.usage.saturating_sub(prev.usage),
+                    )
+                }
+                _ => (0.0, 0),
+            };
+            *snap = Some(cur);
+            IdleStateData { name: Arc::clone(name), residency, entries }
+        })
+        .collect()
+}
+
+/// Read a policy's `time_in_state` histogram and return the share of the
+/// time since the previous call spent at each frequency.
+fn read_time_in_state(policy: &str, snaps: &mut Vec<u64>) -> Vec<FreqResidency> {
+    let content = std::fs::read_to_string(format!(
+        "/sys/devices/system/cpu/cpufreq/{policy}/stats/time_in_state"
+    ))
+    .unwrap_or_default();
+
+    // Lines are `<freq kHz> <time in 10 ms units>`.
+    let rows: Vec<(f32, u64)> = content
+        .lines()
+        .filter_map(|line| {
+            let (freq, time) = line.split_once(char::is_whitespace)?;
+            Some((freq.parse::<f32>().ok()? / 1000.0, time.trim().parse().ok()?))
+        })
+        .collect();
+
+    let fresh = snaps.len() != rows.len();
+    let deltas: Vec<u64> = rows
+        .iter()
+        .enumerate()
+        .map(|(i, &(_, t))| if fresh { 0 } else { t.saturating_sub(snaps[i]) })
+        .collect();
+    let total: u64 = deltas.iter().sum();
+
+    snaps.clear();
+    snaps.extend(rows.iter().map(|&(_, t)| t));
+
+    rows.iter()
+        .zip(deltas)
+        .map(|(&(freq, _), dt)| FreqResidency {
+            freq,
+            residency: if total > 0 { dt as f32 / total as f32 * 100.0 } else { 0.0 },
+        })
+        .collect()
+}
+
+/// Read free/total space and inodes for any mount point via `statvfs`.
+/// Returns (free_gb, total_gb, inodes_free, inodes_total).
+fn read_mount_usage(mount_point: &str) -> (f32, f32, u64, u64) {
+    let Ok(path) = CString::new(mount_point) else { return (0.0, 0.0, 0, 0) };
     unsafe {
         let mut stat: libc::statvfs = std::mem::zeroed();
         if libc::statvfs(path.as_ptr(), &mut stat) == 0 {
             let bs = stat.f_frsize as f64;
             let total = (stat.f_blocks as f64 * bs) / (1024.0 * 1024.0 * 1024.0);
             let free = (stat.f_bavail as f64 * bs) / (1024.0 * 1024.0 * 1024.0);
-            (free as f32, total as f32)
+            (free as f32, total as f32, stat.f_ffree as u64, stat.f_files as u64)
         } else {
-            (0.0, 0.0)
+            (0.0, 0.0, 0, 0)
         }
     }
 }
@@ -327,4 +641,87 @@ fn parse_cpu_stat(rest: &str) -> (u64, u64) {
         }
     }
     (total, idle)
-}
\ No newline at end of file
+}
+
+/// Parse the counters of a `/proc/diskstats` line, after the device name.
+#[inline]
+fn parse_diskstat<'a>(fields: impl Iterator<Item = &'a str>) -> DiskSnap {
+    let mut snap = DiskSnap::default();
+    for (i, tok) in fields.take(10).enumerate() {
+        let v = tok.parse::<u64>().unwrap_or(0);
+        // Fields: 0 reads, 2 sectors read, 4 writes, 6 sectors written, 9 ms doing I/O.
+        match i {
+            0 => snap.reads = v,
+            2 => snap.sectors_read = v,
+            4 => snap.writes = v,
+            6 => snap.sectors_written = v,
+            9 => snap.io_ms = v,
+            _ => {}
+        }
+    }
+    snap
+}
+
+/// Turn two consecutive diskstats snapshots into per-second rates.  With no
+/// previous snapshot (the device's first reading) every rate is 0.
+fn disk_rates(name: &Arc<str>, prev: Option<&DiskSnap>, cur: &DiskSnap, elapsed: f32) -> DiskData {
+    let rate = |field: fn(&DiskSnap) -> u64| match prev {
+        Some(prev) if elapsed > 0.0 => field(cur).saturating_sub(field(prev)) as f32 / elapsed,
+        _ => 0.0,
+    };
+    let mb = SECTOR_BYTES / (1024.0 * 1024.0);
+
+    DiskData {
+        name: Arc::clone(name),
+        read_mb_s: rate(|s| s.sectors_read) * mb,
+        write_mb_s: rate(|s| s.sectors_written) * mb,
+        read_iops: rate(|s| s.reads),
+        write_iops: rate(|s| s.writes),
+        // ms of I/O per second of wall time → percent busy.
+        utilization: (rate(|s| s.io_ms) / 10.0).min(100.0),
+    }
+}
+
+/// Parse a `Temperature{mValue=35.9, mType=3, mName=skin, mStatus=0}` line.
+fn parse_hal_temperature(line: &str) -> Option<HalTemperature> {
+    let body = line.strip_prefix("Temperature{")?.strip_suffix('}')?;
+
+    let mut temp = HalTemperature {
+        name: Arc::from(""),
+        kind: TemperatureType::Unknown,
+        value: 0.0,
+        status: ThermalStatus::Unknown,
+    };
+    for field in body.split(", ") {
+        let Some((key, val)) = field.split_once('=') else { continue };
+        match key {
+            "mValue" => temp.value = val.parse().unwrap_or(0.0),
+            "mType" => temp.kind = TemperatureType::from_code(val.parse().unwrap_or(-1)),
+            "mName" => temp.name = Arc::from(val),
+            "mStatus" => temp.status = ThermalStatus::from_code(val.parse().unwrap_or(-1)),
+            _ => {}
+        }
+    }
+    Some(temp)
+}
+
+/// Extract (package, activity) from a resumed-activity or focused-window line:
+/// `topResumedActivity=ActivityRecord{1a2b u0 com.foo/.MainActivity t42}` or
+/// `mCurrentFocus=Window{3c4d u0 com.foo/com.foo.MainActivity}`.
+fn parse_foreground(line: &str) -> Option<(String, String)> {
+    let component = line
+        .split_whitespace()
+        .find(|tok| tok.contains('/'))?
+        .trim_end_matches('}');
+    let (package, activity) = component.split_once('/')?;
+    if package.is_empty() {
+        return None;
+    }
+
+    // A leading dot is shorthand for "relative to the package".
+    let activity = match activity.strip_prefix('.') {
+        Some(rel) => format!("{package}.{rel}"),
+        None => activity.to_owned(),
+    };
+    Some((package.to_owned(), activity))
+}
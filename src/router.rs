@@ -3,15 +3,47 @@
 //! New fields added to [`SystemStats`] (or its nested types) are automatically
 //! exposed as endpoints without any routing changes.
 
-use axum::extract::{Path, State};
-use axum::http::StatusCode;
+use std::cmp::Ordering;
+use std::convert::Infallible;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use axum::body::Bytes;
+use axum::extract::ws::WebSocketUpgrade;
+use axum::extract::{Path, Query, State};
+use axum::http::header::{ACCEPT, CONTENT_TYPE, ETAG, IF_NONE_MATCH, VARY};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::response::{IntoResponse, Response};
-use axum::routing::get;
+use axum::routing::{get, post};
 use axum::{Json, Router};
+use serde::Deserialize;
 use serde_json::Value;
-use tokio::sync::watch;
+use tokio::sync::{broadcast, watch};
+use tokio_stream::wrappers::{BroadcastStream, WatchStream};
+use tokio_stream::{Stream, StreamExt};
+
+use crate::clock::unix_millis;
+use crate::condition::Condition;
+use crate::config::parse_duration;
+use crate::encoding::Encoding;
+use crate::history::SharedHistory;
+use crate::meta::Registry;
+use crate::metrics;
+use crate::schema;
+use crate::store::Store;
+use crate::summary::summarize;
+use crate::types::{AppSwitch, SystemStats};
 
-use crate::types::SystemStats;
+/// Shared handler state — cheap to clone, one copy per request.
+#[derive(Clone)]
+pub struct AppState {
+    pub stats: watch::Receiver<SystemStats>,
+    pub events: broadcast::Sender<AppSwitch>,
+    pub history: SharedHistory,
+    pub store: Option<Arc<Store>>,
+    pub meta: Arc<Registry>,
+}
 
 // ─── Router construction ───────────────────────────────────────────────────
 
@@ -23,6 +55,7 @@ use crate::types::SystemStats;
 /// |--------|-------------------------------|---------------------------------------|
 /// | `GET`  | `/`                           | API index — lists every endpoint      |
 /// | `GET`  | `/stats`                      | Full system stats snapshot            |
+/// | `GET`  | `/stats?fields=<a>,<b.*.c>`   | Dotted paths at any depth, merged     |
 /// | `GET`  | `/<field>`                    | Single top-level field                |
 /// | `GET`  | `/<f1>,<f2>,…`                | Multiple fields in one request        |
 /// | `GET`  | `/cores/<name>`               | Single core by name                   |
@@ -30,66 +63,722 @@ use crate::types::SystemStats;
 /// | `GET`  | `/cores/<name>/<f1>,<f2>,…`   | Multiple core fields                  |
 /// | `GET`  | `/cores/*/<field>`            | Field from every core (wildcard)      |
 /// | `GET`  | `/cores/all/<f1>,<f2>,…`      | Multiple fields from every core       |
-pub fn build(rx: watch::Receiver<SystemStats>) -> Router {
+/// | `GET`  | `/cores[<cond>]/<field>`      | Field from the cores matching `cond`  |
+/// | `GET`  | `/cores?where=&sort=&limit=`  | Filtered, sorted, truncated array     |
+/// | `GET`  | `/<path>?wait=30s`            | Long-poll until the value changes     |
+/// | `GET`  | `/<path>?format=csv`          | CSV, MessagePack, CBOR or plain text  |
+/// | `POST` | `/query`                      | Several paths against one snapshot    |
+/// | `GET`  | `/until?cond=&timeout=`       | Block until a condition holds         |
+/// | `GET`  | `/metrics`                    | Prometheus / OpenMetrics exposition   |
+/// | `GET`  | `/schema`                     | JSON Schema of `/stats`               |
+/// | `GET`  | `/openapi.json`               | OpenAPI 3.1 description of the API    |
+/// | `GET`  | `/meta/<path>`                | Unit, description, source and refresh |
+/// | `GET`  | `/events`                     | Server-Sent Events: app switches      |
+/// | `GET`  | `/history/<path>?since=&step=`| Timestamped series of any path        |
+/// | `GET`  | `/summary/<path>?window=`     | Min/max/mean/stddev/percentiles       |
+/// | `GET`  | `/stream/<path>`              | Server-Sent Events: live path updates |
+/// | `GET`  | `/ws`                         | WebSocket: subscriptions + JSON Patch |
+pub fn build(state: AppState) -> Router {
     Router::new()
         .route("/", get(index))
         .route("/stats", get(stats))
+        .route("/metrics", get(metrics))
+        .route("/schema", get(schema))
+        .route("/openapi.json", get(openapi))
+        .route("/meta", get(meta))
+        .route("/meta/*path", get(meta))
+        .route("/query", post(query))
+        .route("/until", get(until))
+        .route("/events", get(events))
+        .route("/history", get(history))
+        .route("/history/*path", get(history))
+        .route("/summary", get(summary))
+        .route("/summary/*path", get(summary))
+        .route("/stream", get(stream))
+        .route("/stream/*path", get(stream))
+        .route("/ws", get(ws))
         .route("/*path", get(resolve))
-        .with_state(rx)
+        .with_state(state)
 }
 
 // ─── Handlers ──────────────────────────────────────────────────────────────
 
 /// `GET /` — Returns the API index with every available endpoint.
-async fn index(State(rx): State<watch::Receiver<SystemStats>>) -> Json<Value> {
-    let tree = stats_to_value(&rx.borrow());
-    let mut endpoints = vec!["/stats".to_owned()];
+async fn index(State(state): State<AppState>, encoding: Encoding) -> Response {
+    let tree = stats_to_value(&state.stats.borrow());
+    let mut endpoints = vec!["/stats".to_owned(), "/events".to_owned()];
     enumerate_endpoints(&tree, "", &mut endpoints);
 
-    Json(serde_json::json!({
+    encoding.respond(&serde_json::json!({
         "name": "asmo",
         "version": env!("CARGO_PKG_VERSION"),
         "endpoints": endpoints,
         "multi_field": "Combine fields with commas: /battery_level,cpu_temp,gpu_load",
         "wildcard": "Use * or 'all' for arrays: /cores/*/usage  /cores/all/usage,cur_freq",
+        "filter": "Filter arrays in the path or query: /cores[model_name=Cortex-X2]/usage  /cores?where=usage>50&sort=-cur_freq&limit=3",
+        "fields": "Pick dotted paths at any depth from /stats: /stats?fields=gpu_load,cores.*.usage",
+        "events": "GET /events streams app_switch events as Server-Sent Events",
+        "history": "Prefix any path with /history for a time series: /history/cores/*/usage?since=5m&step=10s",
+        "summary": "Prefix any path with /summary for rolling statistics: /summary/cpu_temp?window=1m,5m,15m",
+        "stream": "Prefix any path with /stream for Server-Sent Events: /stream/battery_level?changes_only=true",
+        "long_poll": "Add ?wait=30s (with If-None-Match) to block until a value changes: /battery_level?wait=30s",
+        "metrics": "GET /metrics for Prometheus text, or OpenMetrics with Accept: application/openmetrics-text",
+        "query": "POST a JSON array of paths to /query to resolve them all against one snapshot: [\"/battery_level\", \"/cores/*/usage\"]",
+        "until": "Block until a condition holds, then get that snapshot: /until?cond=battery_level>=80&timeout=2h",
+        "ws": "Subscribe to paths over a WebSocket at /ws for JSON Patch updates",
+        "schema": "GET /schema for the JSON Schema of /stats (units, sources, refresh intervals), /openapi.json for the whole API",
+        "meta": "Prefix any path with /meta for its unit, description, source and refresh interval: /meta/cores/*/usage",
+        "format": "Add ?format=csv|msgpack|cbor|text (or send Accept) for other encodings: /battery_level?format=text",
         "usage": "GET any endpoint to retrieve its data."
     }))
 }
 
-/// `GET /stats` — Returns the full system stats snapshot.
-async fn stats(State(rx): State<watch::Receiver<SystemStats>>) -> Json<SystemStats> {
-    Json(rx.borrow().clone())
+/// Query parameters accepted by every resolved path.
+#[derive(Deserialize)]
+struct ResolveParams {
+    wait: Option<String>,
+    #[serde(rename = "where")]
+    filter: Option<String>,
+    sort: Option<String>,
+    limit: Option<String>,
+}
+
+/// Query parameters accepted by `/stats`.
+#[derive(Deserialize)]
+struct StatsParams {
+    wait: Option<String>,
+    fields: Option<String>,
+}
+
+/// `GET /stats` — Returns the full system stats snapshot, or with `fields`
+/// just the listed dotted paths merged into one object:
+/// `/stats?fields=gpu_load,cores.*.usage,cores.cpu4.cur_freq`.
+async fn stats(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    encoding: Encoding,
+    Query(params): Query<StatsParams>,
+) -> Response {
+    let wait = params.wait.as_deref();
+    let Some(raw) = params.fields.as_deref().filter(|raw| !raw.trim().is_empty()) else {
+        let whole = |tree: &Value| Some(tree.clone());
+        return conditional_resolve(state, &headers, encoding, wait, "stats", whole).await;
+    };
+
+    let path = format!("stats?fields={raw}");
+    let fields = match split_fields(raw) {
+        Ok(fields) => fields,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &e, &path),
+    };
+    let select = |tree: &Value| select_fields(tree, &fields);
+    conditional_resolve(state, &headers, encoding, wait, &path, select).await
+}
+
+/// `GET /metrics` — The current snapshot in the Prometheus text format, or
+/// as OpenMetrics when the scraper asks for it; see [`crate::metrics`].
+async fn metrics(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let format = metrics::Format::negotiate(headers.get(ACCEPT).and_then(|v| v.to_str().ok()));
+    let tree = stats_to_value(&state.stats.borrow());
+    ([(CONTENT_TYPE, format.content_type())], metrics::render(&tree, format)).into_response()
+}
+
+/// `GET /schema` — JSON Schema of `/stats`; see [`crate::schema`].
+async fn schema() -> Json<Value> {
+    Json(schema::stats_schema())
+}
+
+/// `GET /openapi.json` — OpenAPI description of every endpoint.
+async fn openapi() -> Json<Value> {
+    Json(schema::openapi())
+}
+
+/// `GET /meta/{path}` — Unit, description, source and refresh interval of
+/// any path the router can resolve; see [`crate::meta`].
+///
+/// Objects and arrays describe their `fields` too, and a comma list in the
+/// last segment (`/meta/cpu_temp,gpu_temp`) answers one entry per field.
+async fn meta(
+    State(state): State<AppState>,
+    path: Option<Path<String>>,
+    encoding: Encoding,
+) -> Response {
+    let path = path.map(|Path(p)| p).unwrap_or_default();
+    let parsed = match ParsedPath::parse(&path) {
+        Ok(parsed) => parsed,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &e, &path),
+    };
+
+    // The schema alone decides: cores and disks exist there before the first poll.
+    match state.meta.describe(&parsed.raw_segments()) {
+        Some(value) => encoding.respond(&value),
+        None => error_response(StatusCode::NOT_FOUND, "not found", &path),
+    }
+}
+
+/// `POST /query` — Resolves a JSON array of paths against one snapshot and
+/// answers an object keyed by path, e.g.
+/// `["/battery_level,battery_status", "/cores?sort=-usage&limit=3"]`.
+///
+/// Each entry is `{"value": …}`, or the usual error object when its path
+/// doesn't resolve, so one bad path doesn't fail the batch.  Paths take
+/// `where`, `sort` and `limit` as they do on their own.  The `ETag` names the
+/// snapshot, as for single paths.  The body is read as JSON whatever its
+/// `Content-Type`, so `curl -d` works.
+async fn query(State(state): State<AppState>, encoding: Encoding, body: Bytes) -> Response {
+    let Ok(paths) = serde_json::from_slice::<Vec<String>>(&body) else {
+        return error_response(StatusCode::BAD_REQUEST, "expected a JSON array of paths", "query");
+    };
+
+    let (seq, tree) = {
+        let stats = state.stats.borrow();
+        (stats.seq, stats_to_value(&stats))
+    };
+    tagged(seq, &query_results(&state.meta, &tree, paths), encoding)
+}
+
+/// The `/query` answer for `paths` against one snapshot's tree.
+fn query_results(meta: &Registry, tree: &Value, paths: Vec<String>) -> Value {
+    let mut results = serde_json::Map::new();
+    for raw in paths {
+        let (path, params) = raw.split_once('?').unwrap_or((&raw, ""));
+        let path = path.trim_start_matches('/');
+        let entry = match query_entry(meta, tree, path, params) {
+            Ok(value) => serde_json::json!({ "value": value }),
+            Err(message) => error_body(&message, path),
+        };
+        results.insert(raw, entry);
+    }
+    Value::Object(results)
+}
+
+/// One `/query` entry: `path` resolved with the `where`, `sort` and `limit`
+/// in its query string.
+fn query_entry(meta: &Registry, tree: &Value, path: &str, params: &str) -> Result<Value, String> {
+    let params: ResolveParams =
+        serde_urlencoded::from_str(params).map_err(|e| format!("invalid query string: {e}"))?;
+    if params.wait.is_some() {
+        return Err("wait is not supported in /query".to_owned());
+    }
+    let path = ParsedPath::parse(path)?;
+    let query = ArrayQuery::parse(&params, &path, meta)?;
+    resolve_query(tree, &path, &query).ok_or_else(|| "not found".to_owned())
+}
+
+/// Query parameters accepted by `/until`.
+#[derive(Deserialize)]
+struct UntilParams {
+    cond: Option<String>,
+    timeout: Option<String>,
+}
+
+/// `GET /until?cond=…` — Blocks until `cond` holds for a published snapshot
+/// and returns that snapshot, or `408` once `timeout` (default 60 s) passes.
+///
+/// See [`crate::condition`] for the syntax, e.g.
+/// `/until?cond=battery_level>=80 and battery_status==Charging`.
+async fn until(
+    State(state): State<AppState>,
+    encoding: Encoding,
+    Query(params): Query<UntilParams>,
+) -> Response {
+    let Some(raw) = params.cond else {
+        return error_response(StatusCode::BAD_REQUEST, "missing cond", "until");
+    };
+    let cond = match Condition::parse(&raw) {
+        Ok(cond) => cond,
+        Err(e) => {
+            return error_response(StatusCode::BAD_REQUEST, &format!("invalid cond: {e}"), "until");
+        }
+    };
+    let timeout = Duration::from_secs(60);
+    let Some(timeout) = parse_duration_param(params.timeout.as_deref(), timeout) else {
+        return error_response(StatusCode::BAD_REQUEST, "invalid timeout", "until");
+    };
+
+    let mut rx = state.stats;
+    let (seq, tree) = {
+        let stats = rx.borrow();
+        (stats.seq, stats_to_value(&stats))
+    };
+    if let Some(path) = unknown_path(&cond, seq, &tree, &state.meta) {
+        return error_response(StatusCode::NOT_FOUND, "not found", path.as_str());
+    }
+
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let (seq, tree) = {
+            let stats = rx.borrow_and_update();
+            (stats.seq, stats_to_value(&stats))
+        };
+        // Sequence 0 is the empty placeholder published before the first poll.
+        if seq > 0 && cond.eval(&tree) {
+            return tagged(seq, &tree, encoding);
+        }
+        if !matches!(tokio::time::timeout_at(deadline, rx.changed()).await, Ok(Ok(()))) {
+            return error_response(StatusCode::REQUEST_TIMEOUT, "condition not met", "until");
+        }
+    }
+}
+
+/// The first path of `cond` that doesn't exist in snapshot `seq`.  Until
+/// the first poll the live tree has no cores or disks yet, so only the
+/// schema can tell a typo from a field that will appear.
+fn unknown_path<'a>(
+    cond: &'a Condition,
+    seq: u64,
+    tree: &Value,
+    meta: &Registry,
+) -> Option<&'a ParsedPath> {
+    cond.paths().into_iter().find(|path| {
+        if seq == 0 {
+            meta.field(&path.raw_segments()).is_none()
+        } else {
+            resolve_request(tree, path).is_none()
+        }
+    })
+}
+
+/// `GET /events` — Streams foreground app switches as Server-Sent Events.
+///
+/// Each event is named `app_switch` and carries an [`AppSwitch`] as JSON.
+/// Slow clients that fall behind simply miss the events they lagged over.
+async fn events(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = BroadcastStream::new(state.events.subscribe()).filter_map(|event| {
+        let event = event.ok()?;
+        Some(Ok(Event::default().event("app_switch").json_data(event).ok()?))
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
 }
 
 /// `GET /{path}` — Resolves an arbitrary path against the current stats.
 ///
 /// Supports comma-separated fields in the last segment and wildcards (`*` / `all`)
 /// for array expansion, e.g. `/cores/*/usage` or `/cores/all/usage,cur_freq`.
+/// Arrays can be filtered in the path (`/cores[usage>50]/usage`), and
+/// `where`, `sort` and `limit` apply to the first array the path expands or
+/// ends at: `/cores?where=usage>50&sort=-cur_freq&limit=3`.
 async fn resolve(
-    State(rx): State<watch::Receiver<SystemStats>>,
+    State(state): State<AppState>,
     Path(path): Path<String>,
+    headers: HeaderMap,
+    encoding: Encoding,
+    Query(params): Query<ResolveParams>,
 ) -> Response {
-    let tree = stats_to_value(&rx.borrow());
+    let parsed = match ParsedPath::parse(&path) {
+        Ok(parsed) => parsed,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &e, &path),
+    };
+    let query = match ArrayQuery::parse(&params, &parsed, &state.meta) {
+        Ok(query) => query,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &e, &path),
+    };
+    let wait = params.wait.as_deref();
+    let select = |tree: &Value| resolve_query(tree, &parsed, &query);
+    conditional_resolve(state, &headers, encoding, wait, &path, select).await
+}
 
-    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+/// Serve the live value `select` picks from the tree, tagged with the sequence number of
+/// the snapshot it came from as a strong `ETag`.
+///
+/// An `If-None-Match` naming the current snapshot gets `304 Not Modified`.
+/// With `wait`, the request instead blocks until the resolved value differs
+/// from what the client already has — the snapshot its `If-None-Match`
+/// names, or else the current one — and answers as soon as it does.  If
+/// nothing changes in time the reply is `304`, or the unchanged value when
+/// the client sent no `If-None-Match`.
+async fn conditional_resolve(
+    state: AppState,
+    headers: &HeaderMap,
+    encoding: Encoding,
+    wait: Option<&str>,
+    path: &str,
+    select: impl Fn(&Value) -> Option<Value>,
+) -> Response {
+    let Some(wait) = parse_duration_param(wait, Duration::ZERO) else {
+        return error_response(StatusCode::BAD_REQUEST, "invalid wait", path);
+    };
 
-    match resolve_request(&tree, &segments) {
-        Some(v) => Json(v).into_response(),
-        None => error_response(StatusCode::NOT_FOUND, "not found", &path),
+    let mut rx = state.stats;
+    let (mut seq, value) = resolve_current(&mut rx, &select);
+    let Some(value) = value else {
+        return error_response(StatusCode::NOT_FOUND, "not found", path);
+    };
+    let known = if_none_match(headers, seq, encoding);
+
+    if wait.is_zero() {
+        return if known == Some(seq) {
+            not_modified(seq, encoding)
+        } else {
+            tagged(seq, &value, encoding)
+        };
+    }
+
+    // Anything the client may have missed in the meantime is answered
+    // straight away.
+    if baseline(&state.history, known, seq, &value, &select).as_ref() != Some(&value) {
+        return tagged(seq, &value, encoding);
+    }
+
+    let deadline = tokio::time::Instant::now() + wait;
+    while let Ok(Ok(())) = tokio::time::timeout_at(deadline, rx.changed()).await {
+        let (next_seq, current) = resolve_current(&mut rx, &select);
+        seq = next_seq;
+        if let Some(current) = current
+            && current != value
+        {
+            return tagged(seq, &current, encoding);
+        }
+    }
+
+    if known.is_some() { not_modified(seq, encoding) } else { tagged(seq, &value, encoding) }
+}
+
+/// What a `?wait` request compares the live value against: what the client
+/// last saw, when its tag names an older snapshot that is still retained,
+/// and the live `value` itself when it names the current one or none.
+/// `None` when the client's snapshot is gone, so any value is news.
+fn baseline(
+    history: &SharedHistory,
+    known: Option<u64>,
+    seq: u64,
+    value: &Value,
+    select: impl Fn(&Value) -> Option<Value>,
+) -> Option<Value> {
+    match known {
+        Some(known) if known != seq => history
+            .read()
+            .ok()
+            .and_then(|history| history.get(known).map(stats_to_value))
+            .and_then(|tree| select(&tree)),
+        _ => Some(value.clone()),
     }
 }
 
+/// Apply `select` to the latest snapshot, marking it seen.
+fn resolve_current(
+    rx: &mut watch::Receiver<SystemStats>,
+    select: &impl Fn(&Value) -> Option<Value>,
+) -> (u64, Option<Value>) {
+    let stats = rx.borrow_and_update();
+    (stats.seq, select(&stats_to_value(&stats)))
+}
+
+/// Tags from a previous run of the server must never match: `seq` starts
+/// again at 1 on every start, so each tag also names the process that issued
+/// it.
+static INSTANCE: LazyLock<String> =
+    LazyLock::new(|| format!("{:x}{:x}", unix_millis(), std::process::id()));
+
+/// Strong `ETag` of snapshot `seq` in `encoding`: `"<instance>-<seq>-<format>"`.
+fn etag(seq: u64, encoding: Encoding) -> String {
+    format!("\"{}-{seq}-{}\"", *INSTANCE, encoding.name())
+}
+
+/// Snapshot sequence number named by `If-None-Match`; `*` means `current`.
+/// Tags issued by another process or for another encoding name nothing.
+fn if_none_match(headers: &HeaderMap, current: u64, encoding: Encoding) -> Option<u64> {
+    let raw = headers.get(IF_NONE_MATCH)?.to_str().ok()?;
+    raw.split(',').map(str::trim).find_map(|tag| {
+        if tag == "*" {
+            return Some(current);
+        }
+        let tag = tag.trim_start_matches("W/").trim_matches('"');
+        let mut parts = tag.splitn(3, '-');
+        let (instance, seq, format) = (parts.next()?, parts.next()?, parts.next()?);
+        if instance != *INSTANCE || format != encoding.name() {
+            return None;
+        }
+        seq.parse().ok()
+    })
+}
+
+/// `200 OK` with `value` in the negotiated encoding and the snapshot's `ETag`.
+fn tagged(seq: u64, value: &Value, encoding: Encoding) -> Response {
+    ([(ETAG, etag(seq, encoding))], encoding.respond(value)).into_response()
+}
+
+/// `304 Not Modified` carrying the snapshot's `ETag`.  The tag depends on
+/// the negotiated encoding, so caches must key on `Accept` here too.
+fn not_modified(seq: u64, encoding: Encoding) -> Response {
+    let headers = [(ETAG, etag(seq, encoding)), (VARY, "Accept".to_owned())];
+    (StatusCode::NOT_MODIFIED, headers).into_response()
+}
+
+/// Query parameters accepted by `/stream`.
+#[derive(Deserialize)]
+struct StreamParams {
+    interval: Option<String>,
+    changes_only: Option<bool>,
+}
+
+/// `GET /stream/{path}` — Pushes the resolved path as a Server-Sent Event
+/// every time the monitor publishes a snapshot.
+///
+/// `interval` throttles delivery to at most one event per period (the latest
+/// snapshot wins), and `changes_only=true` skips events whose value is
+/// identical to the previous one.
+async fn stream(
+    State(state): State<AppState>,
+    path: Option<Path<String>>,
+    Query(params): Query<StreamParams>,
+) -> Response {
+    let path = path.map(|Path(p)| p).unwrap_or_default();
+
+    let Some(interval) = parse_duration_param(params.interval.as_deref(), Duration::ZERO) else {
+        return error_response(StatusCode::BAD_REQUEST, "invalid interval", &path);
+    };
+    let changes_only = params.changes_only.unwrap_or(false);
+
+    let parsed = match ParsedPath::parse(&path) {
+        Ok(parsed) => parsed,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &e, &path),
+    };
+    if resolve_request(&stats_to_value(&state.stats.borrow()), &parsed).is_none() {
+        return error_response(StatusCode::NOT_FOUND, "not found", &path);
+    }
+
+    let mut last: Option<Value> = None;
+    let events = WatchStream::new(state.stats).throttle(interval).filter_map(move |stats| {
+        let value = resolve_request(&stats_to_value(&stats), &parsed)?;
+        if changes_only && last.as_ref() == Some(&value) {
+            return None;
+        }
+        let event = Event::default().json_data(&value).ok()?;
+        last = Some(value);
+        Some(Ok::<_, Infallible>(event))
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
+
+/// `GET /ws` — Upgrades to a WebSocket session; see [`crate::ws`].
+async fn ws(State(state): State<AppState>, upgrade: WebSocketUpgrade) -> Response {
+    upgrade.on_upgrade(move |socket| crate::ws::session(socket, state.stats))
+}
+
+/// Query parameters accepted by `/history`.
+#[derive(Deserialize)]
+struct HistoryParams {
+    since: Option<String>,
+    step: Option<String>,
+}
+
+/// `GET /history/{path}` — Resolves a path against every retained snapshot.
+///
+/// `since` limits how far back to look (default: everything retained) and
+/// `step` thins the series to at most one sample per interval, e.g.
+/// `/history/cores/*/usage?since=5m&step=10s`.
+async fn history(
+    State(state): State<AppState>,
+    path: Option<Path<String>>,
+    encoding: Encoding,
+    Query(params): Query<HistoryParams>,
+) -> Response {
+    let path = path.map(|Path(p)| p).unwrap_or_default();
+
+    let Some(since) = parse_duration_param(params.since.as_deref(), Duration::MAX) else {
+        return error_response(StatusCode::BAD_REQUEST, "invalid since", &path);
+    };
+    let Some(step) = parse_duration_param(params.step.as_deref(), Duration::ZERO) else {
+        return error_response(StatusCode::BAD_REQUEST, "invalid step", &path);
+    };
+
+    let parsed = match ParsedPath::parse(&path) {
+        Ok(parsed) => Arc::new(parsed),
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &e, &path),
+    };
+
+    let since_ms = unix_millis().saturating_sub(since.as_millis().min(u64::MAX as u128) as u64);
+    let step_ms = step.as_millis() as u64;
+    let samples: Vec<Value> = collect_samples(&state, since_ms, step_ms, Arc::clone(&parsed))
+        .await
+        .into_iter()
+        .map(|(timestamp, value)| serde_json::json!({ "timestamp": timestamp, "value": value }))
+        .collect();
+
+    if samples.is_empty() {
+        let tree = stats_to_value(&state.stats.borrow());
+        if resolve_request(&tree, &parsed).is_none() {
+            return error_response(StatusCode::NOT_FOUND, "not found", &path);
+        }
+    }
+
+    // As CSV, one row per sample.
+    if encoding == Encoding::Csv {
+        return encoding.respond(&Value::Array(samples));
+    }
+    encoding.respond(&serde_json::json!({
+        "path": format!("/{path}"),
+        "samples": samples,
+    }))
+}
+
+/// Query parameters accepted by `/summary`.
+#[derive(Deserialize)]
+struct SummaryParams {
+    window: Option<String>,
+}
+
+/// `GET /summary/{path}` — Rolling statistics for every numeric field a path
+/// resolves to, over one or more trailing windows (default `1m,5m,15m`).
+async fn summary(
+    State(state): State<AppState>,
+    path: Option<Path<String>>,
+    encoding: Encoding,
+    Query(params): Query<SummaryParams>,
+) -> Response {
+    let path = path.map(|Path(p)| p).unwrap_or_default();
+    let raw_windows = params.window.as_deref().unwrap_or("1m,5m,15m");
+
+    let mut windows: Vec<(&str, u64)> = Vec::new();
+    for raw in raw_windows.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let Some(window) = parse_duration(raw) else {
+            return error_response(StatusCode::BAD_REQUEST, "invalid window", &path);
+        };
+        windows.push((raw, window.as_millis() as u64));
+    }
+    let longest = windows.iter().map(|&(_, ms)| ms).max().unwrap_or(0);
+
+    let parsed = match ParsedPath::parse(&path) {
+        Ok(parsed) => Arc::new(parsed),
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &e, &path),
+    };
+
+    let now = unix_millis();
+    let since = now.saturating_sub(longest);
+    let resolved = collect_samples(&state, since, 0, Arc::clone(&parsed)).await;
+
+    if resolved.is_empty() {
+        let tree = stats_to_value(&state.stats.borrow());
+        if resolve_request(&tree, &parsed).is_none() {
+            return error_response(StatusCode::NOT_FOUND, "not found", &path);
+        }
+    }
+
+    let mut out = serde_json::Map::new();
+    for (label, ms) in windows {
+        let start = resolved.partition_point(|(t, _)| *t < now.saturating_sub(ms));
+        let values: Vec<&Value> = resolved[start..].iter().map(|(_, v)| v).collect();
+        out.insert(
+            label.to_owned(),
+            serde_json::json!({
+                "samples": values.len(),
+                "value": summarize(&values),
+            }),
+        );
+    }
+
+    encoding.respond(&serde_json::json!({
+        "path": format!("/{path}"),
+        "windows": out,
+    }))
+}
+
+/// What `path` resolves to in each snapshot published since `since`
+/// (Unix ms), oldest first, at most one per `step_ms`.  Anything older than
+/// the in-memory buffer is read from the persistent store, when enabled.
+/// Snapshots are resolved as they are read and never collected whole.
+async fn collect_samples(
+    state: &AppState,
+    since: u64,
+    step_ms: u64,
+    path: Arc<ParsedPath>,
+) -> Vec<(u64, Value)> {
+    // Only the snapshot handles are taken under the lock; serializing and
+    // resolving them happens off the async worker, with the recorder free
+    // to keep appending.
+    let (snapshots, oldest) = match state.history.read() {
+        Ok(history) => {
+            let snapshots = history.query(since, step_ms, |stats| Some(Arc::clone(stats)));
+            (snapshots, history.oldest())
+        }
+        Err(_) => (Vec::new(), None),
+    };
+
+    let memory = {
+        let path = Arc::clone(&path);
+        tokio::task::spawn_blocking(move || {
+            snapshots
+                .into_iter()
+                .filter_map(|(timestamp, stats)| {
+                    Some((timestamp, resolve_request(&stats_to_value(&stats), &path)?))
+                })
+                .collect::<Vec<_>>()
+        })
+        .await
+        .unwrap_or_default()
+    };
+
+    let Some(store) = state.store.clone() else { return memory };
+    if oldest.is_some_and(|oldest| oldest <= since) {
+        return memory;
+    }
+
+    let until = oldest.unwrap_or_else(unix_millis);
+    let mut older = tokio::task::spawn_blocking(move || {
+        store.query(since, until, step_ms, |mut tree| {
+            clean_f32_precision(&mut tree);
+            resolve_request(&tree, &path)
+        })
+    })
+    .await
+    .unwrap_or_default();
+
+    older.extend(memory);
+    older
+}
+
+/// Parse an optional duration query parameter, `None` if malformed.
+fn parse_duration_param(raw: Option<&str>, default: Duration) -> Option<Duration> {
+    match raw {
+        Some(raw) => parse_duration(raw),
+        None => Some(default),
+    }
+}
+
+/// Split a request path into its non-empty segments.  A `/` inside a
+/// predicate (`cores[idle_states/*/usage>0]`) doesn't split.
+pub fn split_path(path: &str) -> Vec<&str> {
+    let mut segments = split_outside_brackets(path, '/');
+    segments.retain(|s| !s.is_empty());
+    segments
+}
+
+/// Split `s` at every `separator` that isn't inside `[…]`.
+fn split_outside_brackets(s: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0_usize;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth = depth.saturating_sub(1),
+            c if c == separator && depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
 /// Build a JSON error response with a hint pointing to the index.
-fn error_response(status: StatusCode, message: &str, path: &str) -> Response {
-    (
-        status,
-        Json(serde_json::json!({
-            "error": message,
-            "path": format!("/{path}"),
-            "hint": "GET / for available endpoints"
-        })),
-    )
-        .into_response()
+pub fn error_response(status: StatusCode, message: &str, path: &str) -> Response {
+    (status, Json(error_body(message, path))).into_response()
+}
+
+/// The body of an [`error_response`], also used for failed `/query` entries.
+pub fn error_body(message: &str, path: &str) -> Value {
+    serde_json::json!({
+        "error": message,
+        "path": format!("/{path}"),
+        "hint": "GET / for available endpoints"
+    })
 }
 
 // ─── Path resolution ───────────────────────────────────────────────────────
@@ -100,7 +789,7 @@ fn error_response(status: StatusCode, message: &str, path: &str) -> Response {
 /// `556.7999877929688` instead of `556.8`.  This function walks the tree after
 /// conversion and casts every float back through `f32` to recover the short
 /// representation.
-fn stats_to_value(stats: &SystemStats) -> Value {
+pub fn stats_to_value(stats: &SystemStats) -> Value {
     let mut tree = serde_json::to_value(stats).unwrap_or_default();
     clean_f32_precision(&mut tree);
     tree
@@ -111,12 +800,12 @@ fn clean_f32_precision(value: &mut Value) {
     match value {
         Value::Number(n) => {
             // Only touch floats — leave integers untouched.
-            if n.as_u64().is_none() && n.as_i64().is_none() {
-                if let Some(f) = n.as_f64() {
-                    if let Some(clean) = serde_json::Number::from_f64((f as f32) as f64) {
-                        *n = clean;
-                    }
-                }
+            if n.as_u64().is_none()
+                && n.as_i64().is_none()
+                && let Some(f) = n.as_f64()
+                && let Some(clean) = serde_json::Number::from_f64((f as f32) as f64)
+            {
+                *n = clean;
             }
         }
         Value::Array(arr) => arr.iter_mut().for_each(clean_f32_precision),
@@ -127,10 +816,91 @@ fn clean_f32_precision(value: &mut Value) {
 
 /// Returns `true` for wildcard tokens (`*` and `all`).
 #[inline]
-fn is_wildcard(s: &str) -> bool {
+pub fn is_wildcard(s: &str) -> bool {
     s == "*" || s == "all"
 }
 
+/// Split a predicate segment, `cores[usage>50]`, into the array field and
+/// its [`Condition`].
+pub fn split_predicate(segment: &str) -> Option<(&str, &str)> {
+    let (key, rest) = segment.split_once('[')?;
+    Some((key, rest.strip_suffix(']')?))
+}
+
+/// A router path with its predicates parsed, resolved against any number of
+/// snapshots without parsing anything again.
+pub struct ParsedPath {
+    raw: String,
+    segments: Vec<Segment>,
+}
+
+/// One step of a [`ParsedPath`].
+enum Segment {
+    /// A field, item name, wildcard, or (last) comma list of fields.
+    Key(String),
+    /// An array field and the condition its items must meet: `cores[usage>50]`.
+    Filter { raw: String, field: String, condition: Condition },
+}
+
+impl ParsedPath {
+    /// Parse a request path; a malformed predicate is an error.
+    pub fn parse(path: &str) -> Result<Self, String> {
+        Self::from_segments(path, split_path(path))
+    }
+
+    /// Parse a dotted `fields` entry: `cores[usage>50].cur_freq`.
+    fn parse_dotted(field: &str) -> Result<Self, String> {
+        let segments = split_outside_brackets(field, '.').into_iter().map(str::trim);
+        Self::from_segments(field, segments.filter(|s| !s.is_empty()).collect())
+    }
+
+    fn from_segments(raw: &str, segments: Vec<&str>) -> Result<Self, String> {
+        let segments = segments
+            .into_iter()
+            .map(|segment| {
+                let Some((field, predicate)) = split_predicate(segment) else {
+                    if segment.contains(['[', ']']) {
+                        let message = format!("unbalanced brackets in '{segment}'");
+                        return Err(format!("invalid predicate: {message}"));
+                    }
+                    return Ok(Segment::Key(segment.to_owned()));
+                };
+                let condition =
+                    Condition::parse(predicate).map_err(|e| format!("invalid predicate: {e}"))?;
+                Ok(Segment::Filter { raw: segment.to_owned(), field: field.to_owned(), condition })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self { raw: raw.to_owned(), segments })
+    }
+
+    /// The path as given.
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    /// The segments as given, for looking the path up in the schema.
+    pub fn raw_segments(&self) -> Vec<&str> {
+        self.segments.iter().map(Segment::raw).collect()
+    }
+
+    /// Whether a wildcard or predicate expands an array along the way.
+    fn expands(&self) -> bool {
+        self.segments.iter().any(|segment| match segment {
+            Segment::Key(key) => is_wildcard(key),
+            Segment::Filter { .. } => true,
+        })
+    }
+}
+
+impl Segment {
+    fn raw(&self) -> &str {
+        match self {
+            Self::Key(key) => key,
+            Self::Filter { raw, .. } => raw,
+        }
+    }
+}
+
 /// Navigate the JSON tree and return the **raw** value at the given path.
 fn navigate(value: &Value, segments: &[&str]) -> Option<Value> {
     if segments.is_empty() {
@@ -158,43 +928,56 @@ fn navigate(value: &Value, segments: &[&str]) -> Option<Value> {
 /// - Comma fields:      `/cpu_temp,gpu_temp`       → `{"cpu_temp": 34.4, …}`
 /// - Wildcard:          `/cores/*/usage`            → `[{"name":"cpu0","usage":…}, …]`
 /// - Wildcard + commas: `/cores/all/usage,cur_freq` → `[{"name":"cpu0","usage":…,"cur_freq":…}, …]`
-fn resolve_request(value: &Value, segments: &[&str]) -> Option<Value> {
-    if segments.is_empty() {
-        return Some(value.clone());
-    }
+/// - Predicate:         `/cores[usage>50]/usage`    → `[{"usage":…}, …]`, matching cores only
+pub fn resolve_request(value: &Value, path: &ParsedPath) -> Option<Value> {
+    resolve_items(value, &path.segments, None)
+}
 
-    let current = segments[0];
-    let rest = &segments[1..];
+/// [`resolve_request`] with `query` applied to the first array the path
+/// expands (wildcard or predicate) or ends at.
+fn resolve_query(value: &Value, path: &ParsedPath, query: &ArrayQuery) -> Option<Value> {
+    resolve_items(value, &path.segments, Some(query))
+}
+
+fn resolve_items(
+    value: &Value,
+    segments: &[Segment],
+    query: Option<&ArrayQuery>,
+) -> Option<Value> {
+    let Some((segment, rest)) = segments.split_first() else {
+        return Some(value.clone());
+    };
     let is_last = rest.is_empty();
 
+    // ── Predicate or wildcard: expand over the items picked ─────────────
+    let current = match segment {
+        Segment::Key(key) if !is_wildcard(key) => key.as_str(),
+        _ => {
+            let (arr, picked) = pick(value, segment)?;
+            let items = picked.into_iter().map(|i| &arr[i]).collect();
+            return expand(arr, items, rest, query);
+        }
+    };
+
     // ── Comma-separated fields (last segment only) ──────────────────────
     if is_last && current.contains(',') {
         return resolve_comma_fields(value, current);
     }
 
-    // ── Wildcard: expand over every item in an array ────────────────────
-    if is_wildcard(current) {
-        let Value::Array(arr) = value else { return None };
-        let results: Vec<Value> = arr
-            .iter()
-            .filter_map(|item| {
-                if is_last {
-                    return Some(item.clone());
-                }
-                resolve_request(item, rest)
-            })
-            .collect();
-        return if results.is_empty() { None } else { Some(Value::Array(results)) };
-    }
-
     // ── Standard navigation ─────────────────────────────────────────────
     match value {
         Value::Object(map) => {
             let child = map.get(current)?;
-            if is_last {
-                Some(serde_json::json!({ current: child }))
-            } else {
-                resolve_request(child, rest)
+            if !is_last {
+                return resolve_items(child, rest, query);
+            }
+            match (child, query) {
+                (Value::Array(arr), Some(query)) => {
+                    let items = query.apply(arr.iter().collect());
+                    let items: Vec<Value> = items.into_iter().cloned().collect();
+                    Some(serde_json::json!({ current: items }))
+                }
+                _ => Some(serde_json::json!({ current: child })),
             }
         }
         Value::Array(arr) => {
@@ -204,13 +987,66 @@ fn resolve_request(value: &Value, segments: &[&str]) -> Option<Value> {
             if is_last {
                 Some(item.clone())
             } else {
-                resolve_request(item, rest)
+                resolve_items(item, rest, query)
             }
         }
         _ => None,
     }
 }
 
+/// The array an array step picks from, and the indices of the items it
+/// picks: those of field `cores` that a predicate (`cores[usage>50]`) holds
+/// for, every item of `value` for a wildcard, or the item of `value` with
+/// that name.  `None` for any other step, or if there is no such array or
+/// item.
+fn pick<'a>(value: &'a Value, segment: &Segment) -> Option<(&'a [Value], Vec<usize>)> {
+    let segment = match segment {
+        Segment::Filter { field, condition, .. } => {
+            let Value::Array(arr) = value.get(field.as_str())? else { return None };
+            let picked = (0..arr.len()).filter(|&i| condition.eval(&arr[i])).collect();
+            return Some((arr, picked));
+        }
+        Segment::Key(key) => key.as_str(),
+    };
+
+    let Value::Array(arr) = value else { return None };
+    if is_wildcard(segment) {
+        return Some((arr, (0..arr.len()).collect()));
+    }
+    let index =
+        arr.iter().position(|item| item.get("name").and_then(Value::as_str) == Some(segment))?;
+    Some((arr, vec![index]))
+}
+
+/// Resolve `rest` in each of `items`, chosen from `arr` by a wildcard or
+/// predicate, after applying `query`.
+///
+/// A filter that matches nothing gives `[]`, but an empty array, or a field
+/// no item has, doesn't resolve.
+fn expand(
+    arr: &[Value],
+    items: Vec<&Value>,
+    rest: &[Segment],
+    query: Option<&ArrayQuery>,
+) -> Option<Value> {
+    let items = match query {
+        Some(query) => query.apply(items),
+        None => items,
+    };
+    let results: Vec<Value> = items
+        .iter()
+        .filter_map(|item| match rest {
+            [] => Some((*item).clone()),
+            rest => resolve_items(item, rest, None),
+        })
+        .collect();
+
+    if results.is_empty() && (arr.is_empty() || !items.is_empty()) {
+        return None;
+    }
+    Some(Value::Array(results))
+}
+
 /// Extract comma-separated fields from a value.
 fn resolve_comma_fields(value: &Value, raw: &str) -> Option<Value> {
     let mut result = serde_json::Map::new();
@@ -222,6 +1058,230 @@ fn resolve_comma_fields(value: &Value, raw: &str) -> Option<Value> {
     if result.is_empty() { None } else { Some(Value::Object(result)) }
 }
 
+// ─── Array queries ─────────────────────────────────────────────────────────
+
+/// `where`, `sort` and `limit` on an array of items.
+#[derive(Default)]
+struct ArrayQuery {
+    filter: Option<Condition>,
+    /// Item paths, each descending when prefixed with `-`.
+    sort: Vec<(String, bool)>,
+    limit: Option<usize>,
+}
+
+impl ArrayQuery {
+    /// Parse the query parameters for `path`.  They need an array to act on,
+    /// one the path expands or ends at; the schema tells which it ends at
+    /// before the first poll has filled any in.
+    fn parse(params: &ResolveParams, path: &ParsedPath, meta: &Registry) -> Result<Self, String> {
+        let filter = match params.filter.as_deref().filter(|f| !f.trim().is_empty()) {
+            Some(filter) => {
+                Some(Condition::parse(filter).map_err(|e| format!("invalid where: {e}"))?)
+            }
+            None => None,
+        };
+        let sort = params
+            .sort
+            .iter()
+            .flat_map(|raw| raw.split(','))
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(|key| match key.strip_prefix('-') {
+                Some(key) => (key.to_owned(), true),
+                None => (key.trim_start_matches('+').to_owned(), false),
+            })
+            .collect();
+        let limit = match params.limit.as_deref() {
+            Some(raw) => Some(raw.trim().parse().map_err(|_| "invalid limit".to_owned())?),
+            None => None,
+        };
+        let query = Self { filter, sort, limit };
+
+        let unused = query.filter.is_some() || !query.sort.is_empty() || query.limit.is_some();
+        if unused && !path.expands() && !meta.is_array(&path.raw_segments()) {
+            return Err("where, sort and limit need a path that reaches an array".to_owned());
+        }
+        Ok(query)
+    }
+
+    /// Filter, then sort (stably), then truncate.
+    fn apply<'a>(&self, mut items: Vec<&'a Value>) -> Vec<&'a Value> {
+        if let Some(filter) = &self.filter {
+            items.retain(|item| filter.eval(item));
+        }
+        if !self.sort.is_empty() {
+            items.sort_by(|a, b| {
+                self.sort
+                    .iter()
+                    .map(|(key, descending)| sort_order(a, b, key, *descending))
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            });
+        }
+        if let Some(limit) = self.limit {
+            items.truncate(limit);
+        }
+        items
+    }
+}
+
+/// Order of two items by the value at `key`: numbers numerically, strings
+/// alphabetically.  Items without the value sort last either way.
+fn sort_order(a: &Value, b: &Value, key: &str, descending: bool) -> Ordering {
+    let segments = split_path(key);
+    let (a, b) = (navigate(a, &segments), navigate(b, &segments));
+    let ordering = match (&a, &b) {
+        (Some(_), None) => return Ordering::Less,
+        (None, Some(_)) => return Ordering::Greater,
+        (None, None) => return Ordering::Equal,
+        (Some(Value::String(a)), Some(Value::String(b))) => a.cmp(b),
+        (Some(Value::Bool(a)), Some(Value::Bool(b))) => a.cmp(b),
+        (Some(a), Some(b)) => match (a.as_f64(), b.as_f64()) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            _ => Ordering::Equal,
+        },
+    };
+    if descending { ordering.reverse() } else { ordering }
+}
+
+// ─── Field selection ───────────────────────────────────────────────────────
+
+/// Split a `fields` list into the segments of each dotted path:
+/// `gpu_load,cores.*.usage` → `[[gpu_load], [cores, *, usage]]`.  Commas
+/// and dots inside a predicate (`cores[cur_freq>1785.6].usage`) don't split.
+fn split_fields(raw: &str) -> Result<Vec<ParsedPath>, String> {
+    let mut fields = split_outside_brackets(raw, ',')
+        .into_iter()
+        .map(ParsedPath::parse_dotted)
+        .collect::<Result<Vec<_>, _>>()?;
+    fields.retain(|field| !field.segments.is_empty());
+    Ok(fields)
+}
+
+/// Resolve every field and merge the results into one tree, objects in the
+/// order requested and array items in snapshot order.  `None` if any field
+/// doesn't resolve.
+///
+/// `gpu_load,cores.*.usage,cores.cpu4.cur_freq` →
+/// `{"gpu_load":5.27,"cores":[{"name":"cpu0","usage":28.5},…,
+/// {"name":"cpu4","usage":12.1,"cur_freq":1785.6},…]}`
+fn select_fields(tree: &Value, fields: &[ParsedPath]) -> Option<Value> {
+    let mut out = Selection::Object(Vec::new());
+    for field in fields {
+        out.merge(select(tree, &field.segments)?);
+    }
+    Some(out.into_value())
+}
+
+/// Like [`resolve_request`], but keeps the value at its place in the tree:
+/// `cores/cpu4/cur_freq` → `{"cores":[{"name":"cpu4","cur_freq":…}]}`.
+/// Array steps pick items as in [`resolve_request`], predicates included.
+fn select(value: &Value, segments: &[Segment]) -> Option<Selection> {
+    let Some((current, rest)) = segments.split_first() else {
+        return Some(Selection::whole(value));
+    };
+
+    if let Some((arr, picked)) = pick(value, current) {
+        let items: Vec<(usize, Selection)> = picked
+            .iter()
+            .filter_map(|&i| Some((i, Selection::named(&arr[i], select(&arr[i], rest)?))))
+            .collect();
+        // As in `expand`: a filter that matches nothing is `[]`.
+        if items.is_empty() && (arr.is_empty() || !picked.is_empty()) {
+            return None;
+        }
+        let items = Selection::Array(items);
+        // A predicate picks from a field of `value` rather than from `value`.
+        return Some(match current {
+            Segment::Filter { field, .. } => Selection::Object(vec![(field.clone(), items)]),
+            Segment::Key(_) => items,
+        });
+    }
+
+    let Segment::Key(current) = current else { return None };
+    match value {
+        Value::Object(_) if rest.is_empty() => {
+            resolve_items(value, segments, None).map(|v| Selection::whole(&v))
+        }
+        Value::Object(map) => {
+            let child = select(map.get(current.as_str())?, rest)?;
+            Some(Selection::Object(vec![(current.clone(), child)]))
+        }
+        _ => None,
+    }
+}
+
+/// Part of the stats tree picked by [`select`].  Array items keep their
+/// index in the snapshot, so parts of the same item merge whether or not it
+/// has a `name`, and merged arrays stay in snapshot order.
+enum Selection {
+    Value(Value),
+    Object(Vec<(String, Selection)>),
+    Array(Vec<(usize, Selection)>),
+}
+
+impl Selection {
+    fn whole(value: &Value) -> Self {
+        match value {
+            Value::Object(map) => {
+                Self::Object(map.iter().map(|(k, v)| (k.clone(), Self::whole(v))).collect())
+            }
+            Value::Array(items) => Self::Array(items.iter().map(Self::whole).enumerate().collect()),
+            other => Self::Value(other.clone()),
+        }
+    }
+
+    /// `selected`, a part of array item `item`, with the item's `name` first.
+    fn named(item: &Value, selected: Self) -> Self {
+        match (item.get("name"), selected) {
+            (Some(name), Self::Object(mut fields)) => {
+                if !fields.iter().any(|(key, _)| key == "name") {
+                    fields.insert(0, ("name".to_owned(), Self::Value(name.clone())));
+                }
+                Self::Object(fields)
+            }
+            (_, selected) => selected,
+        }
+    }
+
+    /// Merge `from` into `self`: objects by key, arrays by snapshot index.
+    /// Anything else is replaced.
+    fn merge(&mut self, from: Self) {
+        match (self, from) {
+            (Self::Object(into), Self::Object(from)) => {
+                for (key, value) in from {
+                    match into.iter_mut().find(|(existing, _)| *existing == key) {
+                        Some((_, existing)) => existing.merge(value),
+                        None => into.push((key, value)),
+                    }
+                }
+            }
+            (Self::Array(into), Self::Array(from)) => {
+                for (index, item) in from {
+                    match into.iter_mut().find(|(existing, _)| *existing == index) {
+                        Some((_, existing)) => existing.merge(item),
+                        None => into.push((index, item)),
+                    }
+                }
+                into.sort_by_key(|(index, _)| *index);
+            }
+            (into, from) => *into = from,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        match self {
+            Self::Value(value) => value,
+            Self::Object(fields) => {
+                Value::Object(fields.into_iter().map(|(k, v)| (k, v.into_value())).collect())
+            }
+            Self::Array(items) => {
+                Value::Array(items.into_iter().map(|(_, item)| item.into_value()).collect())
+            }
+        }
+    }
+}
+
 // ─── Endpoint enumeration ──────────────────────────────────────────────────
 
 /// Recursively discovers every addressable path in a JSON value tree.
@@ -253,3 +1313,283 @@ fn enumerate_endpoints(value: &Value, prefix: &str, out: &mut Vec<String>) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::RwLock;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::config::HistoryConfig;
+    use crate::history::History;
+
+    fn condition(input: &str) -> Condition {
+        Condition::parse(input).unwrap_or_else(|e| panic!("{input}: {e}"))
+    }
+
+    fn if_none_match_header(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_NONE_MATCH, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn etags_name_the_instance_snapshot_and_format() {
+        assert_eq!(etag(7, Encoding::Json), format!("\"{}-7-json\"", *INSTANCE));
+        assert_eq!(etag(7, Encoding::Csv), format!("\"{}-7-csv\"", *INSTANCE));
+        assert!(!INSTANCE.contains('-'));
+    }
+
+    #[test]
+    fn if_none_match_accepts_own_tags_only() {
+        let matched = |value: &str, encoding| {
+            if_none_match(&if_none_match_header(value), 9, encoding)
+        };
+        let own = etag(5, Encoding::Json);
+
+        assert_eq!(matched(&own, Encoding::Json), Some(5));
+        assert_eq!(matched(&format!("W/{own}"), Encoding::Json), Some(5));
+        assert_eq!(matched(&format!("\"other\", {own}"), Encoding::Json), Some(5));
+        assert_eq!(matched("*", Encoding::Json), Some(9));
+        assert_eq!(matched("*", Encoding::Csv), Some(9));
+
+        // Another encoding, another process, or not ours at all.
+        assert_eq!(matched(&own, Encoding::Csv), None);
+        assert_eq!(matched("\"0-5-json\"", Encoding::Json), None);
+        assert_eq!(matched(&format!("\"{}-x-json\"", *INSTANCE), Encoding::Json), None);
+        assert_eq!(matched("\"abc\"", Encoding::Json), None);
+        assert_eq!(if_none_match(&HeaderMap::new(), 9, Encoding::Json), None);
+    }
+
+    #[test]
+    fn wait_compares_against_the_snapshot_the_client_has() {
+        let config = HistoryConfig { retention: Duration::from_secs(10), interval: Duration::ZERO };
+        let history: SharedHistory = Arc::new(RwLock::new(History::new(&config)));
+        for (seq, battery_level) in [(1, 50), (2, 50), (3, 51)] {
+            let stats = SystemStats { seq, battery_level, ..Default::default() };
+            history.write().unwrap().push(seq * 1000, Arc::new(stats));
+        }
+        let select = |tree: &Value| tree.get("battery_level").cloned();
+        let live = json!(51);
+
+        // No tag, or the current snapshot's: wait for the live value to change.
+        assert_eq!(baseline(&history, None, 3, &live, select), Some(json!(51)));
+        assert_eq!(baseline(&history, Some(3), 3, &live, select), Some(json!(51)));
+        // An older snapshot: compare with what it held, which differs here.
+        assert_eq!(baseline(&history, Some(1), 3, &live, select), Some(json!(50)));
+        // A snapshot no longer retained, or never published: nothing to compare.
+        assert_eq!(baseline(&history, Some(0), 3, &live, select), None);
+        assert_eq!(baseline(&history, Some(42), 3, &live, select), None);
+    }
+
+    fn cores() -> Value {
+        json!({
+            "battery_level": 81,
+            "gpu_load": 5.5,
+            "cores": [
+                { "name": "cpu0", "usage": 10.0, "cur_freq": 1000.0, "online": true },
+                { "name": "cpu4", "usage": 80.0, "cur_freq": 2400.0, "online": true },
+                { "name": "cpu7", "usage": 60.0, "cur_freq": 3000.0, "online": false },
+            ],
+        })
+    }
+
+    fn fields(raw: &str) -> Option<Value> {
+        let fields = split_fields(raw).unwrap_or_else(|e| panic!("{raw}: {e}"));
+        select_fields(&cores(), &fields)
+    }
+
+    #[test]
+    fn fields_merge_parts_of_the_same_item() {
+        assert_eq!(
+            fields("cores[usage>50].usage,cores.cpu4.cur_freq"),
+            Some(json!({
+                "cores": [
+                    { "name": "cpu4", "usage": 80.0, "cur_freq": 2400.0 },
+                    { "name": "cpu7", "usage": 60.0 },
+                ],
+            }))
+        );
+        assert_eq!(
+            fields("gpu_load, cores.cpu0.usage ,cores.cpu0.online"),
+            Some(json!({
+                "gpu_load": 5.5,
+                "cores": [{ "name": "cpu0", "usage": 10.0, "online": true }],
+            }))
+        );
+    }
+
+    #[test]
+    fn overlapping_fields_keep_items_in_snapshot_order() {
+        assert_eq!(
+            fields("cores.cpu7.usage,cores.cpu0.usage,cores.*.online"),
+            Some(json!({
+                "cores": [
+                    { "name": "cpu0", "usage": 10.0, "online": true },
+                    { "name": "cpu4", "online": true },
+                    { "name": "cpu7", "usage": 60.0, "online": false },
+                ],
+            }))
+        );
+        // Objects keep the order the fields were asked for in.
+        assert_eq!(
+            fields("cores.cpu4.cur_freq,battery_level,cores[usage>70].usage"),
+            Some(json!({
+                "cores": [{ "name": "cpu4", "cur_freq": 2400.0, "usage": 80.0 }],
+                "battery_level": 81,
+            }))
+        );
+    }
+
+    #[test]
+    fn fields_fail_as_a_whole() {
+        assert_eq!(fields("battery_level,nope"), None);
+        assert_eq!(fields("cores.cpu9.usage"), None);
+        // A filter that matches nothing is an empty list, not a miss.
+        assert_eq!(fields("cores[usage>99].usage"), Some(json!({ "cores": [] })));
+    }
+
+    #[test]
+    fn query_wraps_each_entry_as_a_value_or_an_error() {
+        let paths = [
+            "/battery_level",
+            "/cores?sort=-usage&limit=1",
+            "/nope",
+            "/cores[usage>]",
+            "/battery_level?limit=3",
+            "/cores?wait=5s",
+        ];
+        let results = query_results(
+            &Registry::new(),
+            &cores(),
+            paths.iter().map(|p| p.to_string()).collect(),
+        );
+
+        let error = |message: &str, path: &str| {
+            json!({ "error": message, "path": path, "hint": "GET / for available endpoints" })
+        };
+        assert_eq!(
+            results,
+            json!({
+                "/battery_level": { "value": { "battery_level": 81 } },
+                "/cores?sort=-usage&limit=1": { "value": { "cores": [
+                    { "name": "cpu4", "usage": 80.0, "cur_freq": 2400.0, "online": true },
+                ] } },
+                "/nope": error("not found", "/nope"),
+                "/cores[usage>]": error(
+                    "invalid predicate: expected a value after 'usage'",
+                    "/cores[usage>]",
+                ),
+                "/battery_level?limit=3": error(
+                    "where, sort and limit need a path that reaches an array",
+                    "/battery_level",
+                ),
+                "/cores?wait=5s": error("wait is not supported in /query", "/cores"),
+            })
+        );
+    }
+
+    fn parse_error(path: &str) -> String {
+        match ParsedPath::parse(path) {
+            Ok(_) => panic!("{path} parsed"),
+            Err(e) => e,
+        }
+    }
+
+    fn entry(path: &str, params: &str) -> Result<Value, String> {
+        query_entry(&Registry::new(), &cores(), path, params)
+    }
+
+    #[test]
+    fn paths_split_outside_brackets_only() {
+        assert_eq!(split_path("/cores[idle_states/*/usage>0]/usage/"), [
+            "cores[idle_states/*/usage>0]",
+            "usage",
+        ]);
+        let fields = split_fields("cores[name=='cpu0,cpu4'].usage, gpu_load,").unwrap();
+        let fields: Vec<&str> = fields.iter().map(ParsedPath::as_str).collect();
+        assert_eq!(fields, ["cores[name=='cpu0,cpu4'].usage", " gpu_load"]);
+
+        let path = ParsedPath::parse("cores[usage>50]/usage,cur_freq").unwrap();
+        assert_eq!(path.raw_segments(), ["cores[usage>50]", "usage,cur_freq"]);
+        assert!(path.expands());
+        assert!(!ParsedPath::parse("cores/cpu0/usage").unwrap().expands());
+    }
+
+    #[test]
+    fn a_comma_inside_brackets_belongs_to_the_predicate() {
+        assert_eq!(
+            entry("cores[usage>50]/usage,cur_freq", ""),
+            Ok(json!([
+                { "usage": 80.0, "cur_freq": 2400.0 },
+                { "usage": 60.0, "cur_freq": 3000.0 },
+            ]))
+        );
+        // One literal, `cpu0,cpu4`, that no core is named.
+        assert_eq!(entry("cores[name==cpu0,cpu4]", ""), Ok(json!([])));
+    }
+
+    #[test]
+    fn malformed_predicates_are_errors() {
+        let unbalanced = parse_error("cores[usage>50/usage");
+        assert_eq!(unbalanced, "invalid predicate: unbalanced brackets in 'cores[usage>50/usage'");
+        let stray = parse_error("cores]/usage");
+        assert_eq!(stray, "invalid predicate: unbalanced brackets in 'cores]'");
+        assert_eq!(parse_error("cores[]"), "invalid predicate: unexpected end of condition");
+        assert_eq!(
+            parse_error("cores[usage>]/usage"),
+            "invalid predicate: expected a value after 'usage'"
+        );
+        assert!(split_fields("gpu_load,cores[usage>50.usage").is_err());
+        assert!(split_fields("cores[].usage").is_err());
+    }
+
+    #[test]
+    fn where_sort_and_limit_need_an_array() {
+        let no_array = Err("where, sort and limit need a path that reaches an array".to_owned());
+        assert_eq!(entry("battery_level", "limit=3"), no_array);
+        assert_eq!(entry("cores/cpu0", "sort=usage"), no_array);
+        assert_eq!(entry("gpu_load", "where=gpu_load>1"), no_array);
+        // Empty parameters ask for nothing.
+        assert_eq!(entry("battery_level", "where=&sort="), Ok(json!({ "battery_level": 81 })));
+
+        assert_eq!(
+            entry("cores/*/usage", "sort=-usage&limit=2"),
+            Ok(json!([{ "usage": 80.0 }, { "usage": 60.0 }]))
+        );
+        assert_eq!(
+            entry("cores", "where=online==false"),
+            Ok(json!({ "cores": [
+                { "name": "cpu7", "usage": 60.0, "cur_freq": 3000.0, "online": false },
+            ] }))
+        );
+    }
+
+    #[test]
+    fn limit_zero_is_an_empty_list_and_a_bad_limit_an_error() {
+        assert_eq!(entry("cores", "limit=0"), Ok(json!({ "cores": [] })));
+        assert_eq!(entry("cores/*/usage", "limit=0"), Ok(json!([])));
+        assert_eq!(entry("cores", "limit=-1"), Err("invalid limit".to_owned()));
+        assert_eq!(entry("cores", "limit=two"), Err("invalid limit".to_owned()));
+        assert!(entry("cores", "where=usage>").unwrap_err().starts_with("invalid where: "));
+    }
+
+    #[test]
+    fn until_reports_unknown_fields_by_schema_then_by_snapshot() {
+        let meta = Registry::new();
+        let cond = condition("cores/*/usage>50 and battery_level>80 or nope>1");
+
+        // Before the first poll, cores are known to the schema.
+        let empty = json!({ "battery_level": 0, "cores": [] });
+        let unknown = unknown_path(&cond, 0, &empty, &meta).map(ParsedPath::as_str);
+        assert_eq!(unknown, Some("nope"));
+
+        // After it, a path has to resolve against the snapshot.
+        let unknown = unknown_path(&cond, 1, &empty, &meta).map(ParsedPath::as_str);
+        assert_eq!(unknown, Some("cores/*/usage"));
+
+        let cond = condition("battery_level>80");
+        assert!(unknown_path(&cond, 1, &empty, &meta).is_none());
+    }
+}
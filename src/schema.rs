@@ -0,0 +1,360 @@
+//! JSON Schema and OpenAPI documents generated from the Rust types.
+//!
+//! Field docs in [`crate::types`] become `description`s, and every property
+//! carries `x-unit` (when it has one), `x-source` and `x-refresh` (`500ms`,
+//! `30s` or `static`).  Struct-level `x-source` / `x-refresh` are copied
+//! down to the properties that don't set their own, so each property is
+//! self-describing.
+
+use schemars::generate::{SchemaGenerator, SchemaSettings};
+use serde_json::{Map, Value, json};
+
+use crate::types::SystemStats;
+
+/// Metadata keys a struct hands down to its properties.
+const INHERITED: [&str; 2] = ["x-source", "x-refresh"];
+
+/// `GET /schema` — JSON Schema (2020-12) of `/stats`.
+pub fn stats_schema() -> Value {
+    let generator = SchemaSettings::draft2020_12().for_serialize().into_generator();
+    let mut schema = generator.into_root_schema_for::<SystemStats>().to_value();
+
+    inherit_metadata(&mut schema);
+    if let Some(Value::Object(defs)) = schema.get_mut("$defs") {
+        defs.values_mut().for_each(inherit_metadata);
+    }
+    schema
+}
+
+/// `GET /openapi.json` — OpenAPI 3.1 description of the HTTP API.
+pub fn openapi() -> Value {
+    let mut generator = SchemaSettings::draft2020_12()
+        .for_serialize()
+        .with(|s| {
+            s.definitions_path = "/components/schemas".into();
+            s.meta_schema = None;
+        })
+        .into_generator();
+    let stats = generator.subschema_for::<SystemStats>().to_value();
+    let mut schemas = definitions(&mut generator);
+    schemas.insert("Error".into(), error_schema());
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "asmo",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Real-time Android device stats. Any field of `/stats` is also \
+                            its own endpoint, addressed by its path.",
+        },
+        "paths": {
+            "/": get("API index — lists every endpoint", &[FORMAT], encoded(&json!({}))),
+            "/stats": get_with(
+                "Full system stats snapshot",
+                vec![
+                    query(
+                        "fields",
+                        "Dotted paths to keep, merged into one tree, e.g. \
+                         `gpu_load,cores[usage>50].usage,cores.cpu4.cur_freq`",
+                        false,
+                    ),
+                    param_ref(WAIT),
+                    param_ref(FORMAT),
+                ],
+                with_errors(tagged(&stats), &["400", "404"]),
+            ),
+            "/{path}": {
+                "get": {
+                    "summary": "Any field, array item or wildcard query",
+                    "description": "`/battery_level`, `/cpu_temp,gpu_temp`, `/cores/cpu0/usage`, \
+                                    `/cores/*/usage`, `/cores/all/usage,cur_freq`, \
+                                    `/cores[model_name=Cortex-X2]/usage`. The value is shaped \
+                                    like the matching part of `SystemStats`.",
+                    "parameters": [
+                        path_param(),
+                        query("where", "Keep array items matching a condition: `usage>50`", false),
+                        query("sort", "Item fields to sort by, `-` descending: `-cur_freq`", false),
+                        query("limit", "Keep at most this many items", false),
+                        param_ref(WAIT),
+                        param_ref(FORMAT),
+                    ],
+                    "responses": with_errors(tagged(&json!({})), &["400", "404"]),
+                },
+            },
+            "/query": {
+                "post": {
+                    "summary": "Several paths resolved against the same snapshot",
+                    "description": "Answers an object keyed by the paths as sent. Each entry \
+                                    is `{value}`, or an `Error` if its path doesn't resolve. \
+                                    Paths may carry `where`, `sort` and `limit` in a query \
+                                    string.",
+                    "parameters": [param_ref(FORMAT)],
+                    "requestBody": {
+                        "required": true,
+                        "content": { "application/json": { "schema": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "examples": [[
+                                "/battery_level,battery_status",
+                                "/cores?sort=-usage&limit=3",
+                            ]],
+                        }}},
+                    },
+                    "responses": with_errors(
+                        tagged(&json!({
+                            "type": "object",
+                            "additionalProperties": { "oneOf": [
+                                {
+                                    "type": "object",
+                                    "properties": { "value": {} },
+                                    "required": ["value"],
+                                },
+                                { "$ref": "#/components/schemas/Error" },
+                            ]},
+                        })),
+                        &["400"],
+                    ),
+                },
+            },
+            "/until": get_with(
+                "Block until a condition holds, then return that snapshot",
+                vec![
+                    query("cond", "Condition, e.g. `battery_level>=80 and screen_on==false`", true),
+                    query("timeout", "How long to wait, e.g. `2h` (default 60s)", false),
+                    param_ref(FORMAT),
+                ],
+                with_errors(tagged(&stats), &["400", "404", "408"]),
+            ),
+            "/metrics": get(
+                "Prometheus text exposition, or OpenMetrics when negotiated",
+                &[],
+                json!({ "200": { "description": "Exposition", "content": {
+                    "text/plain": { "schema": { "type": "string" } },
+                    "application/openmetrics-text": { "schema": { "type": "string" } },
+                }}}),
+            ),
+            "/events": get("App switches as Server-Sent Events", &[], event_stream()),
+            "/history/{path}": get_with(
+                "Timestamped series of any path",
+                vec![
+                    path_param(),
+                    query("since", "How far back, e.g. `5m` (default: all retained)", false),
+                    query("step", "At most one sample per interval, e.g. `10s`", false),
+                    param_ref(FORMAT),
+                ],
+                with_errors(encoded(&json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string" },
+                        "samples": { "type": "array", "items": {
+                            "type": "object",
+                            "properties": {
+                                "timestamp": { "type": "integer", "x-unit": "ms" },
+                                "value": {},
+                            },
+                        }},
+                    },
+                })), &["400", "404"]),
+            ),
+            "/summary/{path}": get_with(
+                "Min / max / mean / stddev / percentiles of any path",
+                vec![
+                    path_param(),
+                    query("window", "Comma-separated windows (default `1m,5m,15m`)", false),
+                    param_ref(FORMAT),
+                ],
+                with_errors(encoded(&json!({ "type": "object" })), &["400", "404"]),
+            ),
+            "/stream/{path}": get_with(
+                "Any path as Server-Sent Events, one per snapshot",
+                vec![
+                    path_param(),
+                    query("interval", "At most one event per interval, e.g. `1s`", false),
+                    query("changes_only", "Skip events whose value did not change", false),
+                ],
+                with_errors(event_stream(), &["400", "404"]),
+            ),
+            "/ws": get(
+                "WebSocket: subscribe to paths, receive JSON Patch deltas",
+                &[],
+                json!({ "101": { "description": "Switching to the WebSocket protocol" } }),
+            ),
+            "/meta/{path}": get_with(
+                "Unit, description, source and refresh interval of any path",
+                vec![path_param(), param_ref(FORMAT)],
+                with_errors(encoded(&json!({
+                    "type": "object",
+                    "properties": {
+                        "type": { "type": "string" },
+                        "unit": { "type": ["string", "null"] },
+                        "description": { "type": "string" },
+                        "source": { "type": "string" },
+                        "refresh": { "type": "string" },
+                        "static": { "type": "boolean" },
+                        "values": { "type": "array" },
+                        "fields": { "type": "object" },
+                    },
+                })), &["400", "404"]),
+            ),
+            "/schema": get("JSON Schema of `/stats`", &[], json_only(&json!({}))),
+            "/openapi.json": get("This document", &[], json_only(&json!({}))),
+        },
+        "components": {
+            "schemas": schemas,
+            "parameters": {
+                "wait": query(
+                    "wait",
+                    "Long-poll: block until the value differs from the snapshot named by \
+                     `If-None-Match` (or the current one), e.g. `30s`",
+                    false,
+                ),
+                "format": {
+                    "name": "format",
+                    "in": "query",
+                    "description": "Response encoding; overrides `Accept`",
+                    "schema": {
+                        "type": "string",
+                        "enum": ["json", "csv", "msgpack", "cbor", "text"],
+                    },
+                },
+            },
+        },
+    })
+}
+
+/// Every definition the generator has seen, with metadata handed down.
+fn definitions(generator: &mut SchemaGenerator) -> Map<String, Value> {
+    let mut defs = generator.take_definitions(true);
+    defs.values_mut().for_each(inherit_metadata);
+    defs
+}
+
+/// Copy a struct's [`INHERITED`] keys into each of its properties that
+/// doesn't set them itself.
+fn inherit_metadata(schema: &mut Value) {
+    let Value::Object(object) = schema else { return };
+    let defaults: Vec<(&str, Value)> = INHERITED
+        .iter()
+        .filter_map(|&key| Some((key, object.get(key)?.clone())))
+        .collect();
+    let Some(Value::Object(properties)) = object.get_mut("properties") else { return };
+
+    for property in properties.values_mut() {
+        let Value::Object(property) = property else { continue };
+        for (key, value) in &defaults {
+            property.entry(*key).or_insert_with(|| value.clone());
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// OpenAPI building blocks
+// ---------------------------------------------------------------------------
+
+const WAIT: &str = "wait";
+const FORMAT: &str = "format";
+
+fn get(summary: &str, params: &[&str], responses: Value) -> Value {
+    get_with(summary, params.iter().map(|p| param_ref(p)).collect(), responses)
+}
+
+fn get_with(summary: &str, parameters: Vec<Value>, responses: Value) -> Value {
+    json!({ "get": { "summary": summary, "parameters": parameters, "responses": responses } })
+}
+
+fn param_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/parameters/{name}") })
+}
+
+fn query(name: &str, description: &str, required: bool) -> Value {
+    json!({
+        "name": name,
+        "in": "query",
+        "required": required,
+        "description": description,
+        "schema": { "type": "string" },
+    })
+}
+
+fn path_param() -> Value {
+    json!({
+        "name": "path",
+        "in": "path",
+        "required": true,
+        "description": "Router path; may contain `/`, `*` / `all`, `[condition]` and comma lists",
+        "schema": { "type": "string" },
+    })
+}
+
+/// `200` in every negotiable encoding; see [`crate::encoding`].
+fn encoded(schema: &Value) -> Value {
+    json!({ "200": { "description": "OK", "content": {
+        "application/json": { "schema": schema },
+        "text/csv": { "schema": { "type": "string" } },
+        "application/msgpack": { "schema": schema },
+        "application/cbor": { "schema": schema },
+        "text/plain": { "schema": { "type": "string" } },
+    }}})
+}
+
+/// [`encoded`], plus the snapshot `ETag` and `304` for conditional requests.
+fn tagged(schema: &Value) -> Value {
+    let mut responses = encoded(schema);
+    responses["200"]["headers"] = json!({
+        "ETag": {
+            "description": "Names the server process, snapshot and encoding the value came \
+                            from: `\"<instance>-<seq>-<format>\"`",
+            "schema": { "type": "string" },
+        },
+    });
+    responses["304"] = json!({
+        "description": "Unchanged since the `If-None-Match` snapshot",
+        "headers": { "Vary": { "schema": { "type": "string" } } },
+    });
+    responses
+}
+
+fn json_only(schema: &Value) -> Value {
+    json!({ "200": { "description": "OK", "content": {
+        "application/json": { "schema": schema },
+    }}})
+}
+
+fn event_stream() -> Value {
+    json!({ "200": { "description": "Event stream", "content": {
+        "text/event-stream": { "schema": { "type": "string" } },
+    }}})
+}
+
+fn with_errors(mut responses: Value, statuses: &[&str]) -> Value {
+    for status in statuses {
+        responses[*status] = json!({
+            "description": error_description(status),
+            "content": { "application/json": {
+                "schema": { "$ref": "#/components/schemas/Error" },
+            }},
+        });
+    }
+    responses
+}
+
+fn error_description(status: &str) -> &'static str {
+    match status {
+        "400" => "Invalid query parameter or predicate",
+        "404" => "Path does not resolve",
+        "408" => "Condition not met before the timeout",
+        _ => "Error",
+    }
+}
+
+fn error_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "error": { "type": "string" },
+            "path": { "type": "string" },
+            "hint": { "type": "string" },
+        },
+        "required": ["error", "path", "hint"],
+    })
+}
@@ -0,0 +1,363 @@
+//! Optional on-disk history — an append-only, gzip-compressed segment log.
+//!
+//! Two tiers live under the store directory:
+//!
+//! - `raw/`    every published snapshot, one segment file per hour
+//! - `rollup/` per-bucket averages (1 minute by default), one segment per day
+//!
+//! Segment files are named after the Unix time (ms) they start at and hold
+//! one `{"t": <ms>, "v": <snapshot>}` JSON line per record.  Each flush
+//! appends a new gzip member, so a crash loses at most one flush interval and
+//! never corrupts what was already written: readers skip a damaged member and
+//! resume at the next one.  Expired segments are deleted whole.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use flate2::Compression;
+use flate2::bufread::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::watch;
+
+use crate::clock::unix_millis;
+use crate::config::PersistConfig;
+use crate::summary::average;
+use crate::types::SystemStats;
+
+const RAW_SEGMENT_MS: u64 = 3_600_000; // 1 h
+const ROLLUP_SEGMENT_MS: u64 = 86_400_000; // 1 d
+
+/// ID1, ID2 and CM (deflate) — how every gzip member starts.
+const GZIP_MAGIC: [u8; 3] = [0x1f, 0x8b, 0x08];
+
+#[derive(Serialize, Deserialize)]
+struct Record {
+    t: u64,
+    v: Value,
+}
+
+/// Just the timestamp of a record, to skip it without building its snapshot.
+#[derive(Deserialize)]
+struct Stamp {
+    t: u64,
+}
+
+#[derive(Clone, Copy)]
+enum Tier {
+    Raw,
+    Rollup,
+}
+
+impl Tier {
+    fn dir_name(self) -> &'static str {
+        match self {
+            Self::Raw => "raw",
+            Self::Rollup => "rollup",
+        }
+    }
+
+    fn segment_ms(self) -> u64 {
+        match self {
+            Self::Raw => RAW_SEGMENT_MS,
+            Self::Rollup => ROLLUP_SEGMENT_MS,
+        }
+    }
+}
+
+pub struct Store {
+    dir: PathBuf,
+    raw_retention_ms: u64,
+    rollup_ms: u64,
+    rollup_retention_ms: u64,
+    flush_interval: Duration,
+}
+
+impl Store {
+    pub fn open(config: &PersistConfig) -> io::Result<Self> {
+        let dir = config.dir();
+        fs::create_dir_all(dir.join(Tier::Raw.dir_name()))?;
+        fs::create_dir_all(dir.join(Tier::Rollup.dir_name()))?;
+
+        Ok(Self {
+            dir,
+            raw_retention_ms: config.raw_retention.as_millis() as u64,
+            rollup_ms: (config.rollup_interval.as_millis() as u64).max(1),
+            rollup_retention_ms: config.rollup_retention.as_millis() as u64,
+            flush_interval: config.flush_interval,
+        })
+    }
+
+    /// `select` applied to the records in `[since, until)`, oldest first and
+    /// at most one per `step_ms`; records it returns `None` for are left out.
+    /// Raw snapshots are used for as far back as they are retained, rollups
+    /// for anything older.
+    ///
+    /// Records are decoded one at a time and only what `select` returns is
+    /// kept, so a long range never holds more than one snapshot in memory.
+    pub fn query(
+        &self,
+        since: u64,
+        until: u64,
+        step_ms: u64,
+        mut select: impl FnMut(Value) -> Option<Value>,
+    ) -> Vec<(u64, Value)> {
+        let raw_start = unix_millis().saturating_sub(self.raw_retention_ms).max(since);
+
+        let mut scan = Scan { since, until, step_ms, next_due: 0, out: Vec::new() };
+        if since < raw_start {
+            scan.until = raw_start.min(until);
+            self.read_tier(Tier::Rollup, &mut scan, &mut select);
+        }
+        if raw_start < until {
+            scan.since = raw_start;
+            scan.until = until;
+            self.read_tier(Tier::Raw, &mut scan, &mut select);
+        }
+        scan.out
+    }
+
+    fn read_tier(
+        &self,
+        tier: Tier,
+        scan: &mut Scan,
+        select: &mut impl FnMut(Value) -> Option<Value>,
+    ) {
+        for (start, path) in self.segments(tier) {
+            if start + tier.segment_ms() <= scan.since || start >= scan.until {
+                continue;
+            }
+            let Ok(data) = fs::read(&path) else { continue };
+            for_each_line(&data, |line| scan.record(line, select));
+        }
+    }
+
+    /// Segment files of a tier as (start ms, path), oldest first.
+    fn segments(&self, tier: Tier) -> Vec<(u64, PathBuf)> {
+        let Ok(entries) = fs::read_dir(self.dir.join(tier.dir_name())) else {
+            return Vec::new();
+        };
+
+        let mut segments: Vec<(u64, PathBuf)> = entries
+            .filter_map(Result::ok)
+            .filter_map(|e| {
+                let name = e.file_name();
+                let start = name.to_string_lossy().strip_suffix(".jsonl.gz")?.parse().ok()?;
+                Some((start, e.path()))
+            })
+            .collect();
+        segments.sort_unstable_by_key(|&(start, _)| start);
+        segments
+    }
+
+    /// Append records to their segments, one gzip member per segment touched.
+    fn append(&self, tier: Tier, records: &[Record]) -> io::Result<()> {
+        let segment_ms = tier.segment_ms();
+
+        for chunk in records.chunk_by(|a, b| a.t / segment_ms == b.t / segment_ms) {
+            let start = chunk[0].t / segment_ms * segment_ms;
+            let path = self.dir.join(tier.dir_name()).join(format!("{start}.jsonl.gz"));
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+            let mut gz = GzEncoder::new(file, Compression::default());
+            for record in chunk {
+                serde_json::to_writer(&mut gz, record)?;
+                gz.write_all(b"\n")?;
+            }
+            gz.finish()?.sync_data()?;
+        }
+
+        Ok(())
+    }
+
+    /// Delete segments whose newest possible record is past retention.
+    fn prune(&self, now: u64) {
+        for (tier, retention) in [
+            (Tier::Raw, self.raw_retention_ms),
+            (Tier::Rollup, self.rollup_retention_ms),
+        ] {
+            for (start, path) in self.segments(tier) {
+                if start + tier.segment_ms() < now.saturating_sub(retention) {
+                    let _ = fs::remove_file(path);
+                }
+            }
+        }
+    }
+
+    fn flush(&self, raw: &[Record], rollups: &[Record]) -> io::Result<()> {
+        self.append(Tier::Raw, raw)?;
+        self.append(Tier::Rollup, rollups)?;
+        self.prune(unix_millis());
+        Ok(())
+    }
+}
+
+/// The state of one [`Store::query`].
+struct Scan {
+    since: u64,
+    until: u64,
+    step_ms: u64,
+    next_due: u64,
+    out: Vec<(u64, Value)>,
+}
+
+impl Scan {
+    /// Keep one JSON line if it is in range and due, as `select` maps it.
+    fn record(&mut self, line: &str, select: &mut impl FnMut(Value) -> Option<Value>) {
+        let Ok(Stamp { t }) = serde_json::from_str(line) else { return };
+        if t < self.since || t >= self.until || t < self.next_due {
+            return;
+        }
+        self.next_due = t.saturating_add(self.step_ms);
+
+        let Ok(record) = serde_json::from_str::<Record>(line) else { return };
+        if let Some(value) = select(record.v) {
+            self.out.push((t, value));
+        }
+    }
+}
+
+/// Call `f` with every line of a segment, one gzip member at a time.
+///
+/// A damaged member — the last one of a crash, or one still being written —
+/// gives up the lines decoded before the damage, and reading resumes at the
+/// next member header, so whatever was appended after a crash stays readable.
+fn for_each_line(data: &[u8], mut f: impl FnMut(&str)) {
+    let mut offset = 0;
+    while offset < data.len() {
+        let rest = &data[offset..];
+        let mut decoder = GzDecoder::new(rest);
+        let mut text = String::new();
+        if decoder.read_to_string(&mut text).is_ok() {
+            text.lines().for_each(&mut f);
+            offset = data.len() - decoder.into_inner().len();
+            continue;
+        }
+
+        // The decoder may have run past the damage into the next member and
+        // made up lines from its bytes, so decode again up to the next header
+        // only.  A cut-off last line can't be a whole record.
+        let next = rest[1..].windows(3).position(|w| w == GZIP_MAGIC).map(|i| i + 1);
+        let mut bytes = Vec::new();
+        let _ = GzDecoder::new(&rest[..next.unwrap_or(rest.len())]).read_to_end(&mut bytes);
+        let complete = bytes.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        String::from_utf8_lossy(&bytes[..complete]).lines().for_each(&mut f);
+
+        match next {
+            Some(next) => offset += next,
+            None => break,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Writer task — spawned once when persistence is enabled.
+// ---------------------------------------------------------------------------
+
+pub async fn run_writer(mut rx: watch::Receiver<SystemStats>, store: Arc<Store>) {
+    let mut raw: Vec<Record> = Vec::new();
+    let mut rollups: Vec<Record> = Vec::new();
+    let mut bucket: Vec<Value> = Vec::new();
+    let mut bucket_start = 0_u64;
+
+    let mut flush = tokio::time::interval(store.flush_interval);
+    flush.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            changed = rx.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                let t = unix_millis();
+                let v = serde_json::to_value(&*rx.borrow_and_update()).unwrap_or_default();
+
+                // Close the rollup bucket once a snapshot lands in the next one.
+                let start = t / store.rollup_ms * store.rollup_ms;
+                if start != bucket_start && !bucket.is_empty() {
+                    let refs: Vec<&Value> = bucket.iter().collect();
+                    if let Some(avg) = average(&refs) {
+                        rollups.push(Record { t: bucket_start, v: avg });
+                    }
+                    bucket.clear();
+                }
+                bucket_start = start;
+                bucket.push(v.clone());
+                raw.push(Record { t, v });
+            }
+            _ = flush.tick() => {
+                if raw.is_empty() && rollups.is_empty() {
+                    continue;
+                }
+                let store = Arc::clone(&store);
+                let raw_batch = std::mem::take(&mut raw);
+                let rollup_batch = std::mem::take(&mut rollups);
+                let result = tokio::task::spawn_blocking(move || {
+                    store.flush(&raw_batch, &rollup_batch)
+                })
+                .await;
+                if let Ok(Err(e)) = result {
+                    eprintln!("history store: flush failed: {e}");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(text: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn lines(data: &[u8]) -> Vec<String> {
+        let mut out = Vec::new();
+        for_each_line(data, |line| out.push(line.to_owned()));
+        out
+    }
+
+    #[test]
+    fn reads_every_member_in_order() {
+        let mut data = member("a1\na2\n");
+        data.extend(member("b1\n"));
+        assert_eq!(lines(&data), ["a1", "a2", "b1"]);
+    }
+
+    #[test]
+    fn skips_a_truncated_member_and_resumes_at_the_next() {
+        let damaged: String = (0..200).map(|i| format!("damaged line {i}\n")).collect();
+        let damaged = member(&damaged);
+
+        let mut data = member("before 1\nbefore 2\n");
+        data.extend(&damaged[..damaged.len() / 2]);
+        data.extend(member("after 1\nafter 2\n"));
+
+        let lines = lines(&data);
+        assert_eq!(lines[..2], ["before 1", "before 2"]);
+        assert_eq!(lines[lines.len() - 2..], ["after 1", "after 2"]);
+        // What survived of the damaged member is its own lines, whole ones.
+        assert!(lines.len() > 4);
+        for line in &lines[2..lines.len() - 2] {
+            assert!(line.starts_with("damaged line "), "{line}");
+            line["damaged line ".len()..].parse::<u32>().unwrap();
+        }
+    }
+
+    #[test]
+    fn keeps_the_complete_lines_of_a_member_cut_off_at_the_end() {
+        let mut data = member("first\n");
+        let last = member(&"x".repeat(64).repeat(100));
+        data.extend(&last[..last.len() - 4]);
+        data.extend(b"\x1f\x8b");
+
+        assert_eq!(lines(&data), ["first"]);
+    }
+}
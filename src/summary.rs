@@ -0,0 +1,104 @@
+//! Rolling-window statistics over resolved history samples.
+//!
+//! Every numeric leaf of the newest sample is replaced by the min, max, mean,
+//! standard deviation and percentiles of that same leaf across all samples.
+//! Array items are matched by `"name"` (falling back to position), so per-core
+//! wildcards summarise each core separately.  The same walk also produces the
+//! plain averages used to downsample the persistent store.
+
+use serde_json::{Map, Value};
+
+/// Summarise a series of resolved values (oldest first).
+/// Returns `None` when there are no samples.
+pub fn summarize(samples: &[&Value]) -> Option<Value> {
+    let template = samples.last()?;
+    Some(reduce_node(template, samples, &describe))
+}
+
+/// Average a series of values into one of the same shape: numeric leaves
+/// become their mean, everything else keeps the latest value.
+pub fn average(samples: &[&Value]) -> Option<Value> {
+    let template = samples.last()?;
+    Some(reduce_node(template, samples, &|values: Vec<f64>| {
+        if values.is_empty() {
+            return Value::Null;
+        }
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        serde_json::Number::from_f64(mean).map_or(Value::Null, Value::Number)
+    }))
+}
+
+/// Walk `template` and reduce each numeric leaf across `samples`, which
+/// hold the value found at the same position in every sample.
+fn reduce_node(template: &Value, samples: &[&Value], leaf: &dyn Fn(Vec<f64>) -> Value) -> Value {
+    match template {
+        Value::Number(_) => {
+            let values: Vec<f64> = samples.iter().filter_map(|v| v.as_f64()).collect();
+            leaf(values)
+        }
+        Value::Object(map) => {
+            let out: Map<String, Value> = map
+                .iter()
+                .map(|(key, child)| {
+                    let children: Vec<&Value> =
+                        samples.iter().filter_map(|s| s.get(key)).collect();
+                    (key.clone(), reduce_node(child, &children, leaf))
+                })
+                .collect();
+            Value::Object(out)
+        }
+        Value::Array(items) => {
+            let out: Vec<Value> = items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| {
+                    let name = item.get("name").and_then(Value::as_str);
+                    let children: Vec<&Value> = samples
+                        .iter()
+                        .filter_map(|s| {
+                            let arr = s.as_array()?;
+                            match name {
+                                Some(name) => arr
+                                    .iter()
+                                    .find(|v| v.get("name").and_then(Value::as_str) == Some(name)),
+                                None => arr.get(i),
+                            }
+                        })
+                        .collect();
+                    reduce_node(item, &children, leaf)
+                })
+                .collect();
+            Value::Array(out)
+        }
+        // Strings, booleans and nulls keep their latest value.
+        other => other.clone(),
+    }
+}
+
+/// Descriptive statistics of one numeric series.
+fn describe(mut values: Vec<f64>) -> Value {
+    if values.is_empty() {
+        return Value::Null;
+    }
+    values.sort_unstable_by(f64::total_cmp);
+
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+
+    // Nearest-rank percentile on the sorted series.
+    let percentile = |p: f64| {
+        let rank = ((p / 100.0) * n).ceil() as usize;
+        values[rank.clamp(1, values.len()) - 1]
+    };
+
+    serde_json::json!({
+        "min": values[0],
+        "max": values[values.len() - 1],
+        "mean": mean,
+        "stddev": variance.sqrt(),
+        "p50": percentile(50.0),
+        "p95": percentile(95.0),
+        "p99": percentile(99.0),
+    })
+}
@@ -1,12 +1,13 @@
 use std::sync::Arc;
 
+use schemars::JsonSchema;
 use serde::Serialize;
 
 // ---------------------------------------------------------------------------
 // Battery status as a proper enum — no raw `&'static str` floating around.
 // ---------------------------------------------------------------------------
 
-#[derive(Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[derive(Serialize, JsonSchema, Clone, Copy, Default, PartialEq, Eq)]
 pub enum BatteryStatus {
     Charging,
     Discharging,
@@ -19,6 +20,9 @@ pub enum BatteryStatus {
 }
 
 impl BatteryStatus {
+    pub const ALL: [Self; 5] =
+        [Self::Charging, Self::Discharging, Self::NotCharging, Self::Full, Self::Unknown];
+
     pub fn from_code(code: i32) -> Self {
         match code {
             2 => Self::Charging,
@@ -30,50 +34,544 @@ impl BatteryStatus {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Thermal HAL throttling severity, as reported by `dumpsys thermalservice`.
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize, JsonSchema, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ThermalStatus {
+    None,
+    Light,
+    Moderate,
+    Severe,
+    Critical,
+    Emergency,
+    Shutdown,
+    #[default]
+    #[serde(rename = "N/A")]
+    Unknown,
+}
+
+impl ThermalStatus {
+    pub const ALL: [Self; 8] = [
+        Self::None,
+        Self::Light,
+        Self::Moderate,
+        Self::Severe,
+        Self::Critical,
+        Self::Emergency,
+        Self::Shutdown,
+        Self::Unknown,
+    ];
+
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            0 => Self::None,
+            1 => Self::Light,
+            2 => Self::Moderate,
+            3 => Self::Severe,
+            4 => Self::Critical,
+            5 => Self::Emergency,
+            6 => Self::Shutdown,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Thermal HAL sensor category (`android.os.Temperature.TYPE_*`).
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize, JsonSchema, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TemperatureType {
+    Cpu,
+    Gpu,
+    Battery,
+    Skin,
+    UsbPort,
+    PowerAmplifier,
+    BclVoltage,
+    BclCurrent,
+    BclPercentage,
+    Npu,
+    Tpu,
+    Display,
+    Modem,
+    Soc,
+    #[default]
+    Unknown,
+}
+
+impl TemperatureType {
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            0 => Self::Cpu,
+            1 => Self::Gpu,
+            2 => Self::Battery,
+            3 => Self::Skin,
+            4 => Self::UsbPort,
+            5 => Self::PowerAmplifier,
+            6 => Self::BclVoltage,
+            7 => Self::BclCurrent,
+            8 => Self::BclPercentage,
+            9 => Self::Npu,
+            10 => Self::Tpu,
+            11 => Self::Display,
+            12 => Self::Modem,
+            13 => Self::Soc,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Display power state, as reported by `dumpsys power`.
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize, JsonSchema, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DisplayState {
+    On,
+    Off,
+    Doze,
+    DozeSuspend,
+    Vr,
+    OnSuspend,
+    #[default]
+    #[serde(rename = "N/A")]
+    Unknown,
+}
+
+impl DisplayState {
+    pub const ALL: [Self; 7] = [
+        Self::On,
+        Self::Off,
+        Self::Doze,
+        Self::DozeSuspend,
+        Self::Vr,
+        Self::OnSuspend,
+        Self::Unknown,
+    ];
+
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "ON" => Self::On,
+            "OFF" => Self::Off,
+            "DOZE" => Self::Doze,
+            "DOZE_SUSPEND" => Self::DozeSuspend,
+            "VR" => Self::Vr,
+            "ON_SUSPEND" => Self::OnSuspend,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Whether the panel is lit for normal use (doze / AOD does not count).
+    pub fn is_on(self) -> bool {
+        matches!(self, Self::On | Self::Vr | Self::OnSuspend)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Doze mode, derived from `dumpsys deviceidle` deep and light states.
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize, JsonSchema, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DozeMode {
+    Active,
+    Light,
+    Deep,
+    #[default]
+    #[serde(rename = "N/A")]
+    Unknown,
+}
+
+impl DozeMode {
+    pub const ALL: [Self; 4] = [Self::Active, Self::Light, Self::Deep, Self::Unknown];
+
+    pub fn from_states(deep: &str, light: &str) -> Self {
+        match (deep, light) {
+            ("IDLE" | "IDLE_MAINTENANCE", _) => Self::Deep,
+            (_, "IDLE" | "WAITING_FOR_NETWORK" | "IDLE_MAINTENANCE") => Self::Light,
+            ("", "") => Self::Unknown,
+            _ => Self::Active,
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Main stats payload — sent over the watch channel every tick.
 // `Arc<str>` for strings that never change: cloning is a single atomic inc.
+//
+// Field docs become descriptions in `/schema` and `/openapi.json`, alongside
+// `x-unit`, `x-source` and `x-refresh`.  A struct's `x-source` / `x-refresh`
+// apply to every field that doesn't set its own.
 // ---------------------------------------------------------------------------
 
-#[derive(Serialize, Clone, Default)]
+/// One snapshot of everything asmo reports.
+#[derive(Serialize, JsonSchema, Clone, Default)]
+#[schemars(extend("x-refresh" = "500ms"))]
 pub struct SystemStats {
+    // Publish sequence number (1 for the first snapshot, 0 before it) —
+    // served as the `ETag` rather than as a field.
+    #[serde(skip)]
+    pub seq: u64,
+
+    /// Device manufacturer.
+    #[schemars(extend("x-source" = "getprop ro.product.manufacturer", "x-refresh" = "static"))]
     pub manufacturer: Arc<str>,
+    /// Marketing model name.
+    #[schemars(extend("x-source" = "getprop ro.product.model", "x-refresh" = "static"))]
     pub product_model: Arc<str>,
+    /// SoC model, e.g. `SM8475`.
+    #[schemars(extend("x-source" = "getprop ro.soc.model", "x-refresh" = "static"))]
     pub soc_model: Arc<str>,
+    /// Kernel release.
+    #[schemars(extend("x-source" = "uname -r", "x-refresh" = "static"))]
     pub kernel_version: Arc<str>,
+    /// Android release, e.g. `14`.
+    #[schemars(extend("x-source" = "getprop ro.build.version.release", "x-refresh" = "static"))]
     pub android_version: Arc<str>,
+    /// Extended device identity and build info.
+    #[schemars(extend("x-source" = "getprop", "x-refresh" = "static"))]
+    pub device: Arc<DeviceInfo>,
 
+    /// Time since boot, including deep sleep.
+    #[schemars(extend("x-unit" = "s", "x-source" = "CLOCK_BOOTTIME"))]
     pub uptime_seconds: u64,
+    /// Time since boot spent awake.
+    #[schemars(extend("x-unit" = "s", "x-source" = "CLOCK_MONOTONIC"))]
+    pub awake_seconds: u64,
+    /// Time since boot spent in deep sleep.
+    #[schemars(extend("x-unit" = "s", "x-source" = "CLOCK_BOOTTIME - CLOCK_MONOTONIC"))]
+    pub deep_sleep_seconds: u64,
+    /// Unix time of the last boot.
+    #[schemars(extend(
+        "x-unit" = "s",
+        "x-source" = "wall clock - CLOCK_BOOTTIME",
+        "x-refresh" = "static"
+    ))]
+    pub boot_time: u64,
+    /// Load average over the last minute.
+    #[schemars(extend("x-source" = "/proc/loadavg"))]
+    pub load_avg_1m: f32,
+    /// Load average over the last 5 minutes.
+    #[schemars(extend("x-source" = "/proc/loadavg"))]
+    pub load_avg_5m: f32,
+    /// Load average over the last 15 minutes.
+    #[schemars(extend("x-source" = "/proc/loadavg"))]
+    pub load_avg_15m: f32,
+    /// Runnable tasks right now.
+    #[schemars(extend("x-source" = "/proc/loadavg"))]
+    pub running_tasks: u32,
+    /// Tasks in existence.
+    #[schemars(extend("x-source" = "/proc/loadavg"))]
+    pub total_tasks: u32,
+    /// Battery charge.
+    #[schemars(extend("x-unit" = "%", "x-source" = "dumpsys battery"))]
     pub battery_level: i32,
+    /// Charging state.
+    #[schemars(extend("x-source" = "dumpsys battery"))]
     pub battery_status: BatteryStatus,
+    /// Battery temperature.
+    #[schemars(extend("x-unit" = "°C", "x-source" = "dumpsys battery"))]
     pub battery_temp: f32,
+    /// CPU temperature from the first CPU thermal zone found at startup.
+    #[schemars(extend("x-unit" = "°C", "x-source" = "/sys/class/thermal/thermal_zone*/temp"))]
     pub cpu_temp: f32,
+    /// GPU temperature from the first GPU thermal zone found at startup.
+    #[schemars(extend("x-unit" = "°C", "x-source" = "/sys/class/thermal/thermal_zone*/temp"))]
     pub gpu_temp: f32,
+    /// Share of time the GPU was busy.
+    #[schemars(extend("x-unit" = "%", "x-source" = "/sys/class/kgsl/kgsl-3d0/gpubusy"))]
     pub gpu_load: f32,
+    /// Platform throttling level.
+    #[schemars(extend("x-source" = "dumpsys thermalservice"))]
+    pub thermal_status: ThermalStatus,
+    /// Temperatures the thermal HAL curates for the framework.
+    #[schemars(extend("x-source" = "dumpsys thermalservice"))]
+    pub hal_temperatures: Vec<HalTemperature>,
+    /// RAM in use (total minus available).
+    #[schemars(extend("x-unit" = "MB", "x-source" = "/proc/meminfo"))]
     pub memory_used_mb: f32,
+    /// Installed RAM visible to the kernel.
+    #[schemars(extend("x-unit" = "MB", "x-source" = "/proc/meminfo"))]
     pub memory_total_mb: f32,
+    /// Swap (zram) in use.
+    #[schemars(extend("x-unit" = "MB", "x-source" = "/proc/meminfo"))]
     pub swap_used_mb: f32,
+    /// Swap (zram) size.
+    #[schemars(extend("x-unit" = "MB", "x-source" = "/proc/meminfo"))]
     pub swap_total_mb: f32,
+    /// Free space on `/data`.
+    #[schemars(extend("x-unit" = "GB", "x-source" = "statvfs", "x-refresh" = "30s"))]
     pub storage_free_gb: f32,
+    /// Size of `/data`.
+    #[schemars(extend("x-unit" = "GB", "x-source" = "statvfs", "x-refresh" = "30s"))]
     pub storage_total_gb: f32,
+    /// Display refresh rate apps are rendering at.
+    #[schemars(extend("x-unit" = "Hz", "x-source" = "dumpsys display"))]
     pub refresh_rate: f32,
+    /// Screen brightness from 0.0 (minimum) to 1.0 (maximum).
+    #[schemars(extend("x-source" = "dumpsys display"))]
     pub brightness: f32,
+    /// Whether the panel is lit for normal use; doze / always-on display does not count.
+    #[schemars(extend("x-source" = "dumpsys power"))]
+    pub screen_on: bool,
+    /// Display power state.
+    #[schemars(extend("x-source" = "dumpsys power"))]
+    pub display_state: DisplayState,
+    /// Whether the device is awake or dreaming, as opposed to asleep.
+    #[schemars(extend("x-source" = "dumpsys power"))]
+    pub interactive: bool,
+    /// Whether battery saver is on.
+    #[schemars(extend("x-source" = "settings get global low_power"))]
+    pub battery_saver: bool,
+    /// Doze mode.
+    #[schemars(extend("x-source" = "dumpsys deviceidle"))]
+    pub device_idle: DozeMode,
+    /// Package of the resumed activity.
+    #[schemars(extend("x-source" = "dumpsys activity activities"))]
+    pub foreground_package: Arc<str>,
+    /// Class of the resumed activity.
+    #[schemars(extend("x-source" = "dumpsys activity activities"))]
+    pub foreground_activity: Arc<str>,
 
+    /// Per-core CPU state.
+    #[schemars(extend("x-source" = "/sys/devices/system/cpu"))]
     pub cores: Vec<CoreData>,
+    /// Per-cpufreq-policy frequency residency.
+    #[schemars(extend("x-source" = "/sys/devices/system/cpu/cpufreq"))]
+    pub clusters: Vec<ClusterData>,
+    /// Per-mount filesystem usage.
+    #[schemars(extend("x-source" = "statvfs", "x-refresh" = "30s"))]
+    pub storage: Vec<StorageData>,
+    /// Per-block-device I/O rates.
+    #[schemars(extend("x-source" = "/proc/diskstats"))]
+    pub disks: Vec<DiskData>,
+}
+
+// ---------------------------------------------------------------------------
+// Extended device identity and build info — probed once, shared via one `Arc`.
+// ---------------------------------------------------------------------------
+
+/// Extended device identity and build info.
+#[derive(Serialize, JsonSchema, Default)]
+#[schemars(extend("x-refresh" = "static"))]
+pub struct DeviceInfo {
+    /// Brand, e.g. `Nothing`.
+    #[schemars(extend("x-source" = "getprop ro.product.brand"))]
+    pub brand: Box<str>,
+    /// Device codename.
+    #[schemars(extend("x-source" = "getprop ro.product.device"))]
+    pub codename: Box<str>,
+    /// Build fingerprint.
+    #[schemars(extend("x-source" = "getprop ro.build.fingerprint"))]
+    pub fingerprint: Box<str>,
+    /// Security patch level, `YYYY-MM-DD`.
+    #[schemars(extend("x-source" = "getprop ro.build.version.security_patch"))]
+    pub security_patch: Box<str>,
+    /// Android API level.
+    #[schemars(extend("x-source" = "getprop ro.build.version.sdk"))]
+    pub sdk_int: u32,
+    /// Supported ABIs, preferred first.
+    #[schemars(extend("x-source" = "getprop ro.product.cpu.abilist"))]
+    pub abis: Box<[Box<str>]>,
+    /// Bootloader version.
+    #[schemars(extend("x-source" = "getprop ro.bootloader"))]
+    pub bootloader: Box<str>,
+    /// Modem firmware version.
+    #[schemars(extend("x-source" = "getprop gsm.version.baseband"))]
+    pub baseband: Box<str>,
+    /// SELinux mode: `Enforcing`, `Permissive` or `Disabled`.
+    #[schemars(extend("x-source" = "getenforce"))]
+    pub selinux: Box<str>,
+    /// Installed RAM visible to the kernel.
+    #[schemars(extend("x-unit" = "MB", "x-source" = "/proc/meminfo"))]
+    pub ram_total_mb: f32,
+    /// Physical screen width.
+    #[schemars(extend("x-unit" = "px", "x-source" = "wm size"))]
+    pub screen_width: u32,
+    /// Physical screen height.
+    #[schemars(extend("x-unit" = "px", "x-source" = "wm size"))]
+    pub screen_height: u32,
+    /// Physical screen density.
+    #[schemars(extend("x-unit" = "dpi", "x-source" = "wm density"))]
+    pub screen_density: u32,
+    /// Toolchain the kernel was built with.
+    #[schemars(extend("x-source" = "/proc/version"))]
+    pub kernel_compiler: Box<str>,
 }
 
 // ---------------------------------------------------------------------------
 // Per-core snapshot included in every stats payload.
 // ---------------------------------------------------------------------------
 
-#[derive(Serialize, Clone)]
+/// One CPU core.
+#[derive(Serialize, JsonSchema, Clone)]
+#[schemars(extend("x-refresh" = "500ms"))]
 pub struct CoreData {
+    /// Kernel name, e.g. `cpu0`.
+    #[schemars(extend("x-source" = "lscpu", "x-refresh" = "static"))]
     pub name: Arc<str>,
+    /// Share of the last tick the core was busy.
+    #[schemars(extend("x-unit" = "%", "x-source" = "/proc/stat"))]
     pub usage: f32,
+    /// Core model, e.g. `Cortex-A510`.
+    #[schemars(extend("x-source" = "lscpu", "x-refresh" = "static"))]
     pub model_name: Arc<str>,
+    /// Current frequency.
+    #[schemars(extend(
+        "x-unit" = "MHz",
+        "x-source" = "/sys/devices/system/cpu/cpu*/cpufreq/scaling_cur_freq"
+    ))]
     pub cur_freq: f32,
+    /// Lowest supported frequency.
+    #[schemars(extend("x-unit" = "MHz", "x-source" = "lscpu", "x-refresh" = "static"))]
     pub min_freq: f32,
+    /// Highest supported frequency.
+    #[schemars(extend("x-unit" = "MHz", "x-source" = "lscpu", "x-refresh" = "static"))]
     pub max_freq: f32,
+    /// Time in each cpuidle state over the last tick.
+    #[schemars(extend("x-source" = "/sys/devices/system/cpu/cpu*/cpuidle"))]
+    pub idle_states: Vec<IdleStateData>,
+}
+
+/// Share of the last tick a core spent in one cpuidle state.
+#[derive(Serialize, JsonSchema, Clone)]
+#[schemars(extend("x-source" = "/sys/devices/system/cpu/cpu*/cpuidle", "x-refresh" = "500ms"))]
+pub struct IdleStateData {
+    /// State name, e.g. `WFI`.
+    #[schemars(extend("x-refresh" = "static"))]
+    pub name: Arc<str>,
+    /// Share of the last tick spent in this state.
+    #[schemars(extend("x-unit" = "%"))]
+    pub residency: f32,
+    /// Times the state was entered during the last tick.
+    pub entries: u64,
+}
+
+// ---------------------------------------------------------------------------
+// Per-cpufreq-policy snapshot — one entry per cluster of cores sharing a clock.
+// ---------------------------------------------------------------------------
+
+/// One cpufreq policy: cores that share a clock.
+#[derive(Serialize, JsonSchema, Clone)]
+#[schemars(extend("x-source" = "/sys/devices/system/cpu/cpufreq", "x-refresh" = "static"))]
+pub struct ClusterData {
+    /// Policy name, e.g. `policy0`.
+    pub name: Arc<str>,
+    /// Cores clocked by this policy.
+    #[schemars(extend("x-source" = "/sys/devices/system/cpu/cpufreq/policy*/related_cpus"))]
+    pub cores: Arc<[Arc<str>]>,
+    /// Share of the last tick spent at each frequency.
+    #[schemars(extend(
+        "x-source" = "/sys/devices/system/cpu/cpufreq/policy*/stats/time_in_state",
+        "x-refresh" = "500ms"
+    ))]
+    pub time_in_state: Vec<FreqResidency>,
+}
+
+/// Share of the last tick a cluster spent at one frequency (MHz).
+#[derive(Serialize, JsonSchema, Clone)]
+#[schemars(extend(
+    "x-source" = "/sys/devices/system/cpu/cpufreq/policy*/stats/time_in_state",
+    "x-refresh" = "500ms"
+))]
+pub struct FreqResidency {
+    /// Frequency step.
+    #[schemars(extend("x-unit" = "MHz"))]
+    pub freq: f32,
+    /// Share of the last tick spent at this frequency.
+    #[schemars(extend("x-unit" = "%"))]
+    pub residency: f32,
+}
+
+// ---------------------------------------------------------------------------
+// Curated temperature reported by the Android thermal HAL.
+// ---------------------------------------------------------------------------
+
+/// One temperature curated by the thermal HAL.
+#[derive(Serialize, JsonSchema, Clone)]
+#[schemars(extend("x-source" = "dumpsys thermalservice", "x-refresh" = "500ms"))]
+pub struct HalTemperature {
+    /// Sensor name, e.g. `skin`.
+    pub name: Arc<str>,
+    /// Sensor category.
+    #[serde(rename = "type")]
+    pub kind: TemperatureType,
+    /// Temperature.
+    #[schemars(extend("x-unit" = "°C"))]
+    pub value: f32,
+    /// Throttling severity this sensor alone would call for.
+    pub status: ThermalStatus,
+}
+
+// ---------------------------------------------------------------------------
+// Per-mount filesystem usage — refreshed on the slow storage tick.
+// ---------------------------------------------------------------------------
+
+/// One mounted volume.
+#[derive(Serialize, JsonSchema, Clone)]
+#[schemars(extend("x-source" = "statvfs", "x-refresh" = "30s"))]
+pub struct StorageData {
+    /// Short name, e.g. `data` or `sdcard`.
+    #[schemars(extend("x-source" = "config / /proc/mounts", "x-refresh" = "static"))]
+    pub name: Arc<str>,
+    /// Where the volume is mounted.
+    #[schemars(extend("x-source" = "config / /proc/mounts", "x-refresh" = "static"))]
+    pub mount_point: Arc<str>,
+    /// Space available to apps.
+    #[schemars(extend("x-unit" = "GB"))]
+    pub free_gb: f32,
+    /// Size of the volume.
+    #[schemars(extend("x-unit" = "GB"))]
+    pub total_gb: f32,
+    /// Inodes available to apps.
+    pub inodes_free: u64,
+    /// Inodes on the volume.
+    pub inodes_total: u64,
+}
+
+// ---------------------------------------------------------------------------
+// Per-block-device I/O rates, computed from `/proc/diskstats` deltas.
+// ---------------------------------------------------------------------------
+
+/// I/O rates of one block device over the last tick.
+#[derive(Serialize, JsonSchema, Clone)]
+#[schemars(extend("x-source" = "/proc/diskstats", "x-refresh" = "500ms"))]
+pub struct DiskData {
+    /// Block device name, e.g. `sda`.
+    #[schemars(extend("x-source" = "/sys/block", "x-refresh" = "static"))]
+    pub name: Arc<str>,
+    /// Read throughput.
+    #[schemars(extend("x-unit" = "MB/s"))]
+    pub read_mb_s: f32,
+    /// Write throughput.
+    #[schemars(extend("x-unit" = "MB/s"))]
+    pub write_mb_s: f32,
+    /// Completed reads per second.
+    #[schemars(extend("x-unit" = "ops/s"))]
+    pub read_iops: f32,
+    /// Completed writes per second.
+    #[schemars(extend("x-unit" = "ops/s"))]
+    pub write_iops: f32,
+    /// Share of the last tick the device had I/O in flight.
+    #[schemars(extend("x-unit" = "%"))]
+    pub utilization: f32,
+}
+
+// ---------------------------------------------------------------------------
+// Event emitted whenever the foreground app or activity changes.
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize, Clone)]
+pub struct AppSwitch {
+    pub timestamp: u64,
+    pub from_package: Arc<str>,
+    pub from_activity: Arc<str>,
+    pub to_package: Arc<str>,
+    pub to_activity: Arc<str>,
 }
 
 // ---------------------------------------------------------------------------
@@ -85,6 +583,12 @@ pub struct StaticCoreInfo {
     pub model_name: Arc<str>,
     pub min_freq: f32,
     pub max_freq: f32,
+    pub idle_states: Box<[Arc<str>]>,
+}
+
+pub struct StaticClusterInfo {
+    pub name: Arc<str>,
+    pub cores: Arc<[Arc<str>]>,
 }
 
 #[derive(Default)]
@@ -93,16 +597,41 @@ pub struct CpuSnap {
     pub idle: u64,
 }
 
+#[derive(Default, Clone, Copy)]
+pub struct IdleSnap {
+    pub time_us: u64,
+    pub usage: u64,
+}
+
+#[derive(Default)]
+pub struct DiskSnap {
+    pub reads: u64,
+    pub sectors_read: u64,
+    pub writes: u64,
+    pub sectors_written: u64,
+    pub io_ms: u64,
+}
+
 pub struct StaticDeviceInfo {
     pub manufacturer: Arc<str>,
     pub product_model: Arc<str>,
     pub soc_model: Arc<str>,
     pub kernel_version: Arc<str>,
     pub android_version: Arc<str>,
+    pub device: Arc<DeviceInfo>,
+    pub boot_time: u64,
     pub cores: Box<[StaticCoreInfo]>,
+    pub clusters: Box<[StaticClusterInfo]>,
+}
+
+pub struct StorageMount {
+    pub name: Arc<str>,
+    pub mount_point: Arc<str>,
 }
 
 pub struct DevicePaths {
     pub cpu_temp: Box<str>,
     pub gpu_temp: Box<str>,
+    pub storage: Box<[StorageMount]>,
+    pub block_devices: Box<[Arc<str>]>,
 }
\ No newline at end of file
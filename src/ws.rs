@@ -0,0 +1,324 @@
+//! WebSocket sessions with per-client subscriptions and JSON Patch deltas.
+//!
+//! A client subscribes to a set of router paths at its own rate:
+//!
+//! ```json
+//! {"type": "subscribe", "id": "dash", "paths": ["battery_level", "cores/*/usage"], "interval": "2s"}
+//! ```
+//!
+//! The server answers with a `snapshot` holding every path's resolved value,
+//! keyed by path, then sends a `patch` — an RFC 6902 JSON Patch against the
+//! previously sent state — only when something actually changed.
+//! `{"type": "unsubscribe", "id": "dash"}` stops a subscription.  A path
+//! that doesn't resolve when subscribing is refused with the same 404 body
+//! the HTTP routes send, tagged with the subscription's `id`.
+
+use std::time::{Duration, Instant};
+
+use axum::extract::ws::{Message, WebSocket};
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use tokio::sync::watch;
+
+use crate::config::parse_duration;
+use crate::router::{ParsedPath, error_body, resolve_request, stats_to_value};
+use crate::types::SystemStats;
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ClientMessage {
+    Subscribe {
+        id: String,
+        paths: Vec<String>,
+        interval: Option<String>,
+    },
+    Unsubscribe {
+        id: String,
+    },
+}
+
+struct Subscription {
+    id: String,
+    paths: Vec<ParsedPath>,
+    interval: Duration,
+    next_at: Instant,
+    last: Value,
+}
+
+impl Subscription {
+    /// Resolve every subscribed path, keyed by path; unresolvable paths are `null`.
+    fn resolve(&self, tree: &Value) -> Value {
+        let out: Map<String, Value> = self
+            .paths
+            .iter()
+            .map(|path| {
+                let value = resolve_request(tree, path).unwrap_or(Value::Null);
+                (path.as_str().to_owned(), value)
+            })
+            .collect();
+        Value::Object(out)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Session loop — one per connected client.
+// ---------------------------------------------------------------------------
+
+pub async fn session(mut socket: WebSocket, mut rx: watch::Receiver<SystemStats>) {
+    let mut subs: Vec<Subscription> = Vec::new();
+
+    loop {
+        tokio::select! {
+            msg = socket.recv() => {
+                let text = match msg {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+                let reply = handle_message(&text, &mut subs, &rx);
+                if socket.send(Message::Text(reply.to_string())).await.is_err() {
+                    break;
+                }
+            }
+            changed = rx.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                let tree = stats_to_value(&rx.borrow_and_update());
+                let now = Instant::now();
+
+                for sub in subs.iter_mut().filter(|s| now >= s.next_at) {
+                    sub.next_at = now + sub.interval;
+                    let current = sub.resolve(&tree);
+                    let mut ops = Vec::new();
+                    diff(&sub.last, &current, &mut String::new(), &mut ops);
+                    sub.last = current;
+                    if ops.is_empty() {
+                        continue;
+                    }
+
+                    let msg = json!({ "type": "patch", "id": sub.id, "patch": ops });
+                    if socket.send(Message::Text(msg.to_string())).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Apply a client message and return the reply to send back.
+fn handle_message(
+    text: &str,
+    subs: &mut Vec<Subscription>,
+    rx: &watch::Receiver<SystemStats>,
+) -> Value {
+    let msg = match serde_json::from_str::<ClientMessage>(text) {
+        Ok(msg) => msg,
+        Err(e) => return json!({ "type": "error", "message": e.to_string() }),
+    };
+
+    match msg {
+        ClientMessage::Subscribe { id, paths, interval } => {
+            let interval = match interval.as_deref().map(parse_duration) {
+                None => Duration::ZERO,
+                Some(Some(interval)) => interval,
+                Some(None) => {
+                    return json!({ "type": "error", "id": id, "message": "invalid interval" });
+                }
+            };
+            let parsed = paths.iter().map(|path| ParsedPath::parse(path));
+            let paths = match parsed.collect::<Result<Vec<_>, _>>() {
+                Ok(paths) => paths,
+                Err(e) => return json!({ "type": "error", "id": id, "message": e }),
+            };
+            let tree = stats_to_value(&rx.borrow());
+            let missing = paths.iter().find(|path| resolve_request(&tree, path).is_none());
+            if let Some(path) = missing {
+                return not_found(&id, path);
+            }
+
+            let mut sub = Subscription {
+                id,
+                paths,
+                interval,
+                next_at: Instant::now() + interval,
+                last: Value::Null,
+            };
+            sub.last = sub.resolve(&tree);
+
+            let reply = json!({ "type": "snapshot", "id": sub.id, "data": sub.last });
+            subs.retain(|s| s.id != sub.id);
+            subs.push(sub);
+            reply
+        }
+        ClientMessage::Unsubscribe { id } => {
+            subs.retain(|s| s.id != id);
+            json!({ "type": "unsubscribed", "id": id })
+        }
+    }
+}
+
+/// Error frame for a subscription path that doesn't resolve: the HTTP 404
+/// body with the frame's `type`, the subscription `id` and the status.
+fn not_found(id: &str, path: &ParsedPath) -> Value {
+    let mut frame = Map::new();
+    frame.insert("type".to_owned(), json!("error"));
+    frame.insert("id".to_owned(), json!(id));
+    frame.insert("status".to_owned(), json!(404));
+    if let Value::Object(body) = error_body("not found", path.as_str()) {
+        frame.extend(body);
+    }
+    Value::Object(frame)
+}
+
+// ---------------------------------------------------------------------------
+// RFC 6902 diff
+// ---------------------------------------------------------------------------
+
+/// Append the operations that turn `old` into `new` to `ops`.
+///
+/// Objects are diffed key by key and equal-length arrays element by element;
+/// anything else that differs is replaced wholesale.  `pointer` is the JSON
+/// Pointer of the current node and is restored before returning.
+fn diff(old: &Value, new: &Value, pointer: &mut String, ops: &mut Vec<Value>) {
+    match (old, new) {
+        (Value::Object(a), Value::Object(b)) => {
+            for key in a.keys().filter(|k| !b.contains_key(*k)) {
+                ops.push(json!({ "op": "remove", "path": child(pointer, key) }));
+            }
+            for (key, value) in b {
+                match a.get(key) {
+                    Some(prev) => {
+                        let len = pointer.len();
+                        pointer.push_str(&child("", key));
+                        diff(prev, value, pointer, ops);
+                        pointer.truncate(len);
+                    }
+                    None => {
+                        let path = child(pointer, key);
+                        ops.push(json!({ "op": "add", "path": path, "value": value }));
+                    }
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => {
+            for (i, (prev, value)) in a.iter().zip(b).enumerate() {
+                let len = pointer.len();
+                pointer.push_str(&format!("/{i}"));
+                diff(prev, value, pointer, ops);
+                pointer.truncate(len);
+            }
+        }
+        _ if old != new => {
+            ops.push(json!({ "op": "replace", "path": pointer.as_str(), "value": new }));
+        }
+        _ => {}
+    }
+}
+
+/// JSON Pointer of `key` under `pointer`, escaping `~` and `/`.
+fn child(pointer: &str, key: &str) -> String {
+    format!("{pointer}/{}", key.replace('~', "~0").replace('/', "~1"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ops(old: Value, new: Value) -> Vec<Value> {
+        let mut ops = Vec::new();
+        diff(&old, &new, &mut String::new(), &mut ops);
+        ops
+    }
+
+    #[test]
+    fn equal_values_produce_no_ops() {
+        let tree = json!({ "a": 1, "b": [1, 2, { "c": "x" }] });
+        assert!(ops(tree.clone(), tree).is_empty());
+    }
+
+    #[test]
+    fn objects_diff_into_remove_add_and_replace() {
+        let old = json!({ "kept": 1, "gone": 2, "nested": { "x": 1, "y": 2 } });
+        let new = json!({ "kept": 1, "nested": { "x": 1, "y": 3 }, "fresh": [1] });
+
+        assert_eq!(
+            ops(old, new),
+            vec![
+                json!({ "op": "remove", "path": "/gone" }),
+                json!({ "op": "replace", "path": "/nested/y", "value": 3 }),
+                json!({ "op": "add", "path": "/fresh", "value": [1] }),
+            ]
+        );
+    }
+
+    #[test]
+    fn arrays_diff_by_index_unless_their_length_changes() {
+        let old = json!({ "usage": [{ "usage": 10.0 }, { "usage": 20.0 }] });
+        let new = json!({ "usage": [{ "usage": 10.0 }, { "usage": 25.0 }] });
+        assert_eq!(
+            ops(old.clone(), new),
+            vec![json!({ "op": "replace", "path": "/usage/1/usage", "value": 25.0 })]
+        );
+
+        let shorter = json!({ "usage": [{ "usage": 10.0 }] });
+        assert_eq!(
+            ops(old, shorter.clone()),
+            vec![json!({ "op": "replace", "path": "/usage", "value": shorter["usage"] })]
+        );
+    }
+
+    #[test]
+    fn subscription_keys_are_escaped_as_pointer_tokens() {
+        let old = json!({ "cores/*/usage": [{ "usage": 1.0 }], "a~b": 1 });
+        let new = json!({ "cores/*/usage": [{ "usage": 2.0 }], "a~b": 2, "c~/d": null });
+
+        assert_eq!(
+            ops(old, new),
+            vec![
+                json!({ "op": "replace", "path": "/cores~1*~1usage/0/usage", "value": 2.0 }),
+                json!({ "op": "replace", "path": "/a~0b", "value": 2 }),
+                json!({ "op": "add", "path": "/c~0~1d", "value": null }),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_type_change_replaces_the_whole_node() {
+        assert_eq!(
+            ops(json!({ "a": { "b": 1 } }), json!({ "a": 1 })),
+            vec![json!({ "op": "replace", "path": "/a", "value": 1 })]
+        );
+        assert_eq!(
+            ops(json!(1), json!(2)),
+            vec![json!({ "op": "replace", "path": "", "value": 2 })]
+        );
+    }
+
+    #[test]
+    fn subscribing_to_a_missing_path_is_refused_with_a_404_frame() {
+        let (_tx, rx) = watch::channel(SystemStats::default());
+        let mut subs = Vec::new();
+
+        let msg = r#"{"type":"subscribe","id":"dash","paths":["battery_level","nope/x"]}"#;
+        let reply = handle_message(msg, &mut subs, &rx);
+
+        assert_eq!(
+            reply,
+            json!({
+                "type": "error",
+                "id": "dash",
+                "status": 404,
+                "error": "not found",
+                "path": "/nope/x",
+                "hint": "GET / for available endpoints"
+            })
+        );
+        assert!(subs.is_empty());
+
+        let msg = r#"{"type":"subscribe","id":"dash","paths":["battery_level"]}"#;
+        assert_eq!(handle_message(msg, &mut subs, &rx)["type"], "snapshot");
+        assert_eq!(subs.len(), 1);
+    }
+}